@@ -0,0 +1,84 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mso_tri_state::aggregate::Aggregate;
+use mso_tri_state::vec::MsoTriStateVec;
+use mso_tri_state::MsoTriState;
+
+const LEN: usize = 1 << 16;
+
+fn values(seed: usize) -> Vec<MsoTriState> {
+    let core = [
+        MsoTriState::msoFalse,
+        MsoTriState::msoTriStateMixed,
+        MsoTriState::msoTrue,
+    ];
+    (0..LEN).map(|i| core[(i * 7 + seed) % 3]).collect()
+}
+
+fn and(c: &mut Criterion) {
+    let (a, b) = (values(0), values(1));
+    let (va, vb): (MsoTriStateVec, MsoTriStateVec) =
+        (a.iter().copied().collect(), b.iter().copied().collect());
+
+    let mut group = c.benchmark_group("and");
+    group.bench_function("Vec<MsoTriState>", |bench| {
+        bench.iter(|| {
+            black_box(&a)
+                .iter()
+                .zip(black_box(&b))
+                .map(|(&x, &y)| x & y)
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("MsoTriStateVec", |bench| {
+        bench.iter(|| black_box(&va) & black_box(&vb))
+    });
+    group.finish();
+}
+
+fn not(c: &mut Criterion) {
+    let a = values(0);
+    let va: MsoTriStateVec = a.iter().copied().collect();
+
+    let mut group = c.benchmark_group("not");
+    group.bench_function("Vec<MsoTriState>", |bench| {
+        bench.iter(|| black_box(&a).iter().map(|&x| !x).collect::<Vec<_>>())
+    });
+    group.bench_function("MsoTriStateVec", |bench| bench.iter(|| !black_box(&va)));
+    group.finish();
+}
+
+fn count(c: &mut Criterion) {
+    let a = values(0);
+    let va: MsoTriStateVec = a.iter().copied().collect();
+
+    let mut group = c.benchmark_group("count");
+    group.bench_function("Vec<MsoTriState>", |bench| {
+        bench.iter(|| {
+            black_box(&a)
+                .iter()
+                .filter(|&&x| x == MsoTriState::msoTriStateMixed)
+                .count()
+        })
+    });
+    group.bench_function("MsoTriStateVec", |bench| {
+        bench.iter(|| black_box(&va).count(MsoTriState::msoTriStateMixed))
+    });
+    group.finish();
+}
+
+fn aggregate(c: &mut Criterion) {
+    let a = vec![MsoTriState::msoTrue; LEN];
+    let va: MsoTriStateVec = a.iter().copied().collect();
+
+    let mut group = c.benchmark_group("aggregate");
+    group.bench_function("Vec<MsoTriState>", |bench| {
+        bench.iter(|| black_box(&a).iter().copied().aggregate())
+    });
+    group.bench_function("MsoTriStateVec", |bench| {
+        bench.iter(|| black_box(&va).aggregate())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, and, not, count, aggregate);
+criterion_main!(benches);
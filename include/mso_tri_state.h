@@ -0,0 +1,74 @@
+/*
+ * C bindings for mso-tri-state: fearless booleans.
+ *
+ * Link against the static library built with:
+ *     cargo rustc --release --lib --crate-type staticlib --features ffi
+ */
+#ifndef MSO_TRI_STATE_H
+#define MSO_TRI_STATE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Specifies a tri-state Boolean value. The values match the Office constants. */
+typedef enum MsoTriState {
+    msoCTrue = 1,
+    msoFalse = 0,
+    msoTriStateMixed = -2,
+    msoTriStateToggle = -3,
+    msoTrue = -1,
+} MsoTriState;
+
+/* Returned on success. */
+#define MSO_TRI_STATE_OK 0
+/* Returned when a pointer argument is null. */
+#define MSO_TRI_STATE_ERROR_NULL (-1)
+/* Returned when a value can't be converted or parsed. */
+#define MSO_TRI_STATE_ERROR_INVALID (-2)
+
+/*
+ * Every MsoTriState argument must hold one of the values above. Use mso_tri_state_from_int to
+ * check an untrusted integer first.
+ */
+
+/* Converts an Office integer value, writing it to out. */
+int mso_tri_state_from_int(int32_t value, MsoTriState *out);
+
+/* Converts to the Office integer value. */
+int32_t mso_tri_state_to_int(MsoTriState m);
+
+/* Converts from a bool. */
+MsoTriState mso_tri_state_from_bool(bool b);
+
+/* Converts msoTrue or msoFalse to a bool, writing it to out. */
+int mso_tri_state_to_bool(MsoTriState m, bool *out);
+
+/* Kleene logic, with msoTriStateMixed as unknown. */
+MsoTriState mso_tri_state_not(MsoTriState a);
+MsoTriState mso_tri_state_and(MsoTriState a, MsoTriState b);
+MsoTriState mso_tri_state_or(MsoTriState a, MsoTriState b);
+MsoTriState mso_tri_state_xor(MsoTriState a, MsoTriState b);
+
+/*
+ * Writes the Office name of the value into buf as a NUL-terminated string, truncating it to fit
+ * len bytes. Returns the length of the name without the NUL, like snprintf.
+ */
+size_t mso_tri_state_to_string(MsoTriState m, char *buf, size_t len);
+
+/*
+ * Parses a NUL-terminated string, writing the value to out. Only the Office names are accepted
+ * unless lenient is true, in which case any case, the names without their mso or msoTriState
+ * prefix, and the Office integer values are also accepted.
+ */
+int mso_tri_state_parse(const char *s, bool lenient, MsoTriState *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MSO_TRI_STATE_H */
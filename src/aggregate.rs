@@ -0,0 +1,184 @@
+//! Aggregating many values into one, as Office does for a selection.
+//!
+//! ```
+//! use mso_tri_state::aggregate::Aggregate;
+//! use mso_tri_state::MsoTriState;
+//!
+//! let bold = vec![true, true, false];
+//! assert_eq!(bold.into_iter().aggregate(), MsoTriState::msoTriStateMixed);
+//!
+//! let italic: MsoTriState = vec![false, false].into_iter().collect();
+//! assert_eq!(italic, MsoTriState::msoFalse);
+//! ```
+
+use crate::MsoTriState;
+use core::iter::FromIterator;
+
+/// How `msoCTrue` members are aggregated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CTruePolicy {
+    /// `msoCTrue` agrees with `msoTrue`, and the result is `msoTrue`.
+    AsTrue,
+    /// `msoCTrue` only agrees with itself, and disagrees with `msoTrue`.
+    Distinct,
+}
+
+/// How values are aggregated.
+///
+/// Members which are `msoTriStateMixed` or `msoTriStateToggle` always make the result
+/// `msoTriStateMixed`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AggregatePolicy {
+    /// The result for no values.
+    pub empty: MsoTriState,
+    /// How `msoCTrue` members are aggregated.
+    pub ctrue: CTruePolicy,
+}
+
+impl Default for AggregatePolicy {
+    /// Returns a policy where no values aggregate to `msoFalse` and `msoCTrue` is `msoTrue`.
+    fn default() -> AggregatePolicy {
+        AggregatePolicy {
+            empty: MsoTriState::msoFalse,
+            ctrue: CTruePolicy::AsTrue,
+        }
+    }
+}
+
+/// Extends iterators of `MsoTriState` or `bool` with aggregation.
+pub trait Aggregate: Iterator {
+    /// Aggregates the values with the default policy.
+    ///
+    /// The result is `msoTrue` if all values are true, `msoFalse` if all are false, and
+    /// `msoTriStateMixed` otherwise.
+    fn aggregate(self) -> MsoTriState;
+
+    /// Aggregates the values with the given policy.
+    ///
+    /// Stops consuming the iterator once the result is known to be `msoTriStateMixed`.
+    fn aggregate_with(self, policy: AggregatePolicy) -> MsoTriState;
+}
+
+impl<I> Aggregate for I
+where
+    I: Iterator,
+    I::Item: Into<MsoTriState>,
+{
+    fn aggregate(self) -> MsoTriState {
+        self.aggregate_with(AggregatePolicy::default())
+    }
+
+    fn aggregate_with(self, policy: AggregatePolicy) -> MsoTriState {
+        let mut result = None;
+        for m in self {
+            let m = match m.into() {
+                MsoTriState::msoCTrue if policy.ctrue == CTruePolicy::AsTrue => {
+                    MsoTriState::msoTrue
+                }
+                MsoTriState::msoTriStateMixed | MsoTriState::msoTriStateToggle => {
+                    return MsoTriState::msoTriStateMixed
+                }
+                m => m,
+            };
+            match result {
+                None => result = Some(m),
+                Some(r) if r == m => {}
+                Some(_) => return MsoTriState::msoTriStateMixed,
+            }
+        }
+        result.unwrap_or(policy.empty)
+    }
+}
+
+impl FromIterator<MsoTriState> for MsoTriState {
+    fn from_iter<I: IntoIterator<Item = MsoTriState>>(iter: I) -> MsoTriState {
+        iter.into_iter().aggregate()
+    }
+}
+
+impl FromIterator<bool> for MsoTriState {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> MsoTriState {
+        iter.into_iter().aggregate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    #[test]
+    fn aggregate() {
+        assert_eq!(vec![msoTrue, msoTrue].into_iter().aggregate(), msoTrue);
+        assert_eq!(vec![msoFalse].into_iter().aggregate(), msoFalse);
+        assert_eq!(
+            vec![msoTrue, msoFalse].into_iter().aggregate(),
+            msoTriStateMixed
+        );
+        assert_eq!(
+            vec![msoTrue, msoTriStateMixed].into_iter().aggregate(),
+            msoTriStateMixed
+        );
+        assert_eq!(
+            vec![msoFalse, msoTriStateToggle].into_iter().aggregate(),
+            msoTriStateMixed
+        );
+        assert_eq!(vec![msoCTrue, msoTrue].into_iter().aggregate(), msoTrue);
+        assert_eq!(Vec::<bool>::new().into_iter().aggregate(), msoFalse);
+    }
+
+    #[test]
+    fn from_iter() {
+        assert_eq!(
+            vec![true, true].into_iter().collect::<MsoTriState>(),
+            msoTrue
+        );
+        assert_eq!(
+            vec![true, false].into_iter().collect::<MsoTriState>(),
+            msoTriStateMixed
+        );
+        assert_eq!(
+            vec![msoFalse, msoFalse]
+                .into_iter()
+                .collect::<MsoTriState>(),
+            msoFalse
+        );
+    }
+
+    #[test]
+    fn policy() {
+        let policy = AggregatePolicy {
+            empty: msoTriStateMixed,
+            ctrue: CTruePolicy::Distinct,
+        };
+        assert_eq!(
+            Vec::<MsoTriState>::new().into_iter().aggregate_with(policy),
+            msoTriStateMixed
+        );
+        assert_eq!(
+            vec![msoCTrue, msoCTrue].into_iter().aggregate_with(policy),
+            msoCTrue
+        );
+        assert_eq!(
+            vec![msoCTrue, msoTrue].into_iter().aggregate_with(policy),
+            msoTriStateMixed
+        );
+    }
+
+    #[test]
+    fn short_circuit() {
+        let mut seen = 0;
+        let result = [true, false, true, true]
+            .iter()
+            .copied()
+            .inspect(|_| seen += 1)
+            .aggregate();
+        assert_eq!(result, msoTriStateMixed);
+        assert_eq!(seen, 2);
+
+        let result = std::iter::once(msoTriStateMixed)
+            .chain(std::iter::repeat(msoTrue))
+            .aggregate();
+        assert_eq!(result, msoTriStateMixed);
+    }
+}
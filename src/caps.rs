@@ -0,0 +1,235 @@
+//! Which `MsoTriState` values each Office property accepts and returns.
+//!
+//! Office documents most tri-state values as "Not supported.", but the supported set varies by
+//! property. `PROPERTIES` describes the known ones, so writes can be validated before they reach
+//! Office:
+//!
+//! ```
+//! use mso_tri_state::caps::PropertyCaps;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let bold = PropertyCaps::lookup("Font.Bold").unwrap();
+//! assert!(bold.accepts(msoTriStateToggle));
+//! assert!(bold.can_return(msoTriStateMixed));
+//! assert!(!bold.accepts(msoTriStateMixed));
+//! ```
+
+use crate::MsoTriState::{self, *};
+use core::fmt;
+
+/// The values an Office property accepts and returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PropertyCaps {
+    /// The property, such as `Shape.Visible`.
+    pub name: &'static str,
+    /// The values which can be written. Empty for read-only properties.
+    pub writes: &'static [MsoTriState],
+    /// The values which can be read back.
+    pub reads: &'static [MsoTriState],
+}
+
+const TRUE_FALSE: &[MsoTriState] = &[msoTrue, msoFalse];
+const TRUE_FALSE_TOGGLE: &[MsoTriState] = &[msoTrue, msoFalse, msoTriStateToggle];
+const TRUE_FALSE_MIXED: &[MsoTriState] = &[msoTrue, msoFalse, msoTriStateMixed];
+
+const fn read_write(name: &'static str) -> PropertyCaps {
+    PropertyCaps {
+        name,
+        writes: TRUE_FALSE,
+        reads: TRUE_FALSE,
+    }
+}
+
+const fn read_only(name: &'static str) -> PropertyCaps {
+    PropertyCaps {
+        name,
+        writes: &[],
+        reads: TRUE_FALSE,
+    }
+}
+
+/// A property of a range of objects, which reports `msoTriStateMixed` when they disagree.
+const fn range(name: &'static str) -> PropertyCaps {
+    PropertyCaps {
+        name,
+        writes: TRUE_FALSE,
+        reads: TRUE_FALSE_MIXED,
+    }
+}
+
+/// A character property, which can also be toggled.
+const fn font(name: &'static str) -> PropertyCaps {
+    PropertyCaps {
+        name,
+        writes: TRUE_FALSE_TOGGLE,
+        reads: TRUE_FALSE_MIXED,
+    }
+}
+
+/// `Shape.Visible`.
+pub const SHAPE_VISIBLE: PropertyCaps = read_write("Shape.Visible");
+/// `Shape.LockAspectRatio`.
+pub const SHAPE_LOCK_ASPECT_RATIO: PropertyCaps = read_write("Shape.LockAspectRatio");
+/// `Shape.HasTextFrame`.
+pub const SHAPE_HAS_TEXT_FRAME: PropertyCaps = read_only("Shape.HasTextFrame");
+/// `Shape.HasChart`.
+pub const SHAPE_HAS_CHART: PropertyCaps = read_only("Shape.HasChart");
+/// `Shape.HasTable`.
+pub const SHAPE_HAS_TABLE: PropertyCaps = read_only("Shape.HasTable");
+/// `ShapeRange.Visible`.
+pub const SHAPE_RANGE_VISIBLE: PropertyCaps = range("ShapeRange.Visible");
+/// `ShapeRange.LockAspectRatio`.
+pub const SHAPE_RANGE_LOCK_ASPECT_RATIO: PropertyCaps = range("ShapeRange.LockAspectRatio");
+/// `FillFormat.Visible`.
+pub const FILL_VISIBLE: PropertyCaps = range("FillFormat.Visible");
+/// `LineFormat.Visible`.
+pub const LINE_VISIBLE: PropertyCaps = range("LineFormat.Visible");
+/// `TextFrame.WordWrap`.
+pub const TEXT_FRAME_WORD_WRAP: PropertyCaps = range("TextFrame.WordWrap");
+/// `Font.Bold`.
+pub const FONT_BOLD: PropertyCaps = font("Font.Bold");
+/// `Font.Italic`.
+pub const FONT_ITALIC: PropertyCaps = font("Font.Italic");
+/// `Font.Underline`.
+pub const FONT_UNDERLINE: PropertyCaps = font("Font.Underline");
+/// `Font.Shadow`.
+pub const FONT_SHADOW: PropertyCaps = font("Font.Shadow");
+/// `Font.Emboss`.
+pub const FONT_EMBOSS: PropertyCaps = font("Font.Emboss");
+/// `Presentation.Saved`.
+pub const PRESENTATION_SAVED: PropertyCaps = read_write("Presentation.Saved");
+/// `Presentation.ReadOnly`.
+pub const PRESENTATION_READ_ONLY: PropertyCaps = read_only("Presentation.ReadOnly");
+
+/// Every known property.
+pub const PROPERTIES: &[PropertyCaps] = &[
+    SHAPE_VISIBLE,
+    SHAPE_LOCK_ASPECT_RATIO,
+    SHAPE_HAS_TEXT_FRAME,
+    SHAPE_HAS_CHART,
+    SHAPE_HAS_TABLE,
+    SHAPE_RANGE_VISIBLE,
+    SHAPE_RANGE_LOCK_ASPECT_RATIO,
+    FILL_VISIBLE,
+    LINE_VISIBLE,
+    TEXT_FRAME_WORD_WRAP,
+    FONT_BOLD,
+    FONT_ITALIC,
+    FONT_UNDERLINE,
+    FONT_SHADOW,
+    FONT_EMBOSS,
+    PRESENTATION_SAVED,
+    PRESENTATION_READ_ONLY,
+];
+
+impl PropertyCaps {
+    /// Returns the known property with the given name.
+    pub fn lookup(name: &str) -> Option<&'static PropertyCaps> {
+        PROPERTIES.iter().find(|p| p.name == name)
+    }
+
+    /// Returns whether the property is read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Returns whether the value can be written to the property.
+    pub fn accepts(&self, value: MsoTriState) -> bool {
+        self.writes.contains(&value)
+    }
+
+    /// Returns whether the property can report the value.
+    pub fn can_return(&self, value: MsoTriState) -> bool {
+        self.reads.contains(&value)
+    }
+
+    /// Checks that the value can be written to the property.
+    pub fn check(&self, value: MsoTriState) -> Result<(), CapsError> {
+        if self.is_read_only() {
+            Err(CapsError::ReadOnly(self.name))
+        } else if !self.accepts(value) {
+            Err(CapsError::NotAccepted {
+                property: self.name,
+                value,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The error returned when a write is rejected by `PropertyCaps::check`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CapsError {
+    /// The property is read-only.
+    ReadOnly(&'static str),
+    /// The property doesn't accept the value.
+    NotAccepted {
+        /// The property.
+        property: &'static str,
+        /// The rejected value.
+        value: MsoTriState,
+    },
+}
+
+impl fmt::Display for CapsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapsError::ReadOnly(property) => write!(f, "{} is read-only", property),
+            CapsError::NotAccepted { property, value } => {
+                write!(f, "{}: {} is not supported", property, value)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        for p in PROPERTIES {
+            assert_eq!(PropertyCaps::lookup(p.name), Some(p));
+        }
+        assert_eq!(PropertyCaps::lookup("Shape.Nonexistent"), None);
+    }
+
+    #[test]
+    fn table() {
+        for p in PROPERTIES {
+            // Commands and states never overlap beyond msoTrue and msoFalse.
+            assert!(!p.accepts(msoTriStateMixed), "{}", p.name);
+            assert!(!p.can_return(msoTriStateToggle), "{}", p.name);
+            // Office never reports msoCTrue, nor accepts it where documented as unsupported.
+            assert!(!p.can_return(msoCTrue), "{}", p.name);
+            assert!(!p.accepts(msoCTrue), "{}", p.name);
+            assert!(
+                p.can_return(msoTrue) && p.can_return(msoFalse),
+                "{}",
+                p.name
+            );
+        }
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(SHAPE_VISIBLE.check(msoTrue), Ok(()));
+        assert_eq!(
+            SHAPE_VISIBLE.check(msoTriStateToggle),
+            Err(CapsError::NotAccepted {
+                property: "Shape.Visible",
+                value: msoTriStateToggle,
+            })
+        );
+        assert_eq!(FONT_BOLD.check(msoTriStateToggle), Ok(()));
+        assert!(SHAPE_HAS_TEXT_FRAME.is_read_only());
+        assert_eq!(
+            SHAPE_HAS_TEXT_FRAME.check(msoTrue),
+            Err(CapsError::ReadOnly("Shape.HasTextFrame"))
+        );
+    }
+}
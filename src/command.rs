@@ -0,0 +1,173 @@
+//! Resolving `msoTriStateToggle` against a current state.
+//!
+//! In the Office object model, setting a tri-state property is a command: `msoTrue`, `msoFalse`
+//! and `msoCTrue` set it, while `msoTriStateToggle` flips it. Reading the property back gives a
+//! state, which is `msoTriStateMixed` when a selection disagrees. `State` and `Command` keep these
+//! apart:
+//!
+//! ```
+//! use mso_tri_state::command::{Command, State};
+//!
+//! assert_eq!(State::Mixed.apply(Command::Toggle), State::True);
+//! ```
+
+use crate::MsoTriState;
+use core::convert::TryFrom;
+use core::fmt;
+
+/// The value of a tri-state property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum State {
+    /// `msoTrue` or `msoCTrue`.
+    True,
+    /// `msoFalse`.
+    False,
+    /// `msoTriStateMixed`.
+    Mixed,
+}
+
+/// A value written to a tri-state property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// `msoTrue` (or `msoCTrue`) for `true`, `msoFalse` for `false`.
+    Set(bool),
+    /// `msoTriStateToggle`.
+    Toggle,
+}
+
+impl State {
+    /// Returns the state after applying the command.
+    ///
+    /// Toggling a mixed state makes it true, as Word does.
+    pub fn apply(self, command: Command) -> State {
+        match (self, command) {
+            (_, Command::Set(true)) => State::True,
+            (_, Command::Set(false)) => State::False,
+            (State::True, Command::Toggle) => State::False,
+            (State::False, Command::Toggle) | (State::Mixed, Command::Toggle) => State::True,
+        }
+    }
+}
+
+impl MsoTriState {
+    /// Returns the state after applying `command` to the current state `self`.
+    ///
+    /// See `State::apply`. Fails if `self` is `msoTriStateToggle` or `command` is
+    /// `msoTriStateMixed`.
+    pub fn apply(self, command: MsoTriState) -> Result<MsoTriState, ApplyError> {
+        let state = State::try_from(self)?;
+        let command = Command::try_from(command)?;
+        Ok(state.apply(command).into())
+    }
+}
+
+impl From<State> for MsoTriState {
+    fn from(s: State) -> MsoTriState {
+        match s {
+            State::True => MsoTriState::msoTrue,
+            State::False => MsoTriState::msoFalse,
+            State::Mixed => MsoTriState::msoTriStateMixed,
+        }
+    }
+}
+
+impl TryFrom<MsoTriState> for State {
+    type Error = ApplyError;
+
+    fn try_from(m: MsoTriState) -> Result<State, ApplyError> {
+        match m {
+            MsoTriState::msoTrue | MsoTriState::msoCTrue => Ok(State::True),
+            MsoTriState::msoFalse => Ok(State::False),
+            MsoTriState::msoTriStateMixed => Ok(State::Mixed),
+            MsoTriState::msoTriStateToggle => Err(ApplyError::NotAState(m)),
+        }
+    }
+}
+
+impl From<Command> for MsoTriState {
+    fn from(c: Command) -> MsoTriState {
+        match c {
+            Command::Set(b) => b.into(),
+            Command::Toggle => MsoTriState::msoTriStateToggle,
+        }
+    }
+}
+
+impl TryFrom<MsoTriState> for Command {
+    type Error = ApplyError;
+
+    fn try_from(m: MsoTriState) -> Result<Command, ApplyError> {
+        match m {
+            MsoTriState::msoTrue | MsoTriState::msoCTrue => Ok(Command::Set(true)),
+            MsoTriState::msoFalse => Ok(Command::Set(false)),
+            MsoTriState::msoTriStateToggle => Ok(Command::Toggle),
+            MsoTriState::msoTriStateMixed => Err(ApplyError::NotACommand(m)),
+        }
+    }
+}
+
+/// The error returned when an `MsoTriState` is used as a state or command it can't be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ApplyError {
+    /// The value can only be written, not read back as a state.
+    NotAState(MsoTriState),
+    /// The value can only be read back, not written as a command.
+    NotACommand(MsoTriState),
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::NotAState(m) => write!(f, "{} is not a state", m),
+            ApplyError::NotACommand(m) => write!(f, "{} is not a command", m),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ApplyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    #[test]
+    fn state_apply() {
+        for &s in &[State::True, State::False, State::Mixed] {
+            assert_eq!(s.apply(Command::Set(true)), State::True);
+            assert_eq!(s.apply(Command::Set(false)), State::False);
+        }
+        assert_eq!(State::True.apply(Command::Toggle), State::False);
+        assert_eq!(State::False.apply(Command::Toggle), State::True);
+        assert_eq!(State::Mixed.apply(Command::Toggle), State::True);
+    }
+
+    #[test]
+    fn mso_tri_state_apply() {
+        assert_eq!(msoTrue.apply(msoTriStateToggle), Ok(msoFalse));
+        assert_eq!(msoCTrue.apply(msoTriStateToggle), Ok(msoFalse));
+        assert_eq!(msoTriStateMixed.apply(msoTriStateToggle), Ok(msoTrue));
+        assert_eq!(msoFalse.apply(msoCTrue), Ok(msoTrue));
+        assert_eq!(msoTriStateMixed.apply(msoFalse), Ok(msoFalse));
+
+        assert_eq!(
+            msoTriStateToggle.apply(msoTrue),
+            Err(ApplyError::NotAState(msoTriStateToggle))
+        );
+        assert_eq!(
+            msoTrue.apply(msoTriStateMixed),
+            Err(ApplyError::NotACommand(msoTriStateMixed))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        for &s in &[State::True, State::False, State::Mixed] {
+            assert_eq!(State::try_from(MsoTriState::from(s)), Ok(s));
+        }
+        for &c in &[Command::Set(true), Command::Set(false), Command::Toggle] {
+            assert_eq!(Command::try_from(MsoTriState::from(c)), Ok(c));
+        }
+    }
+}
@@ -0,0 +1,615 @@
+//! A small expression language over `MsoTriState` values.
+//!
+//! ```text
+//! expr    = or ("->" expr)?
+//! or      = xor ("|" xor)*
+//! xor     = and ("^" and)*
+//! and     = unary ("&" unary)*
+//! unary   = "!" unary | "(" expr ")" | literal | variable
+//! ```
+//!
+//! Literals are the `Display` names of `MsoTriState`, such as `msoTriStateMixed`. Any other
+//! identifier is a variable. Expressions are evaluated with the Kleene operators, and `a -> b`
+//! means `!a | b`.
+//!
+//! ```
+//! use mso_tri_state::expr::Expr;
+//! use mso_tri_state::MsoTriState::*;
+//! use std::collections::BTreeMap;
+//!
+//! let rule: Expr = "bold & !(italic | mixed)".parse().unwrap();
+//! let env: BTreeMap<_, _> = vec![
+//!     ("bold".to_string(), msoTrue),
+//!     ("italic".to_string(), msoFalse),
+//!     ("mixed".to_string(), msoTriStateMixed),
+//! ]
+//! .into_iter()
+//! .collect();
+//! assert_eq!(rule.eval(&env), Ok(msoTriStateMixed));
+//! assert_eq!(rule.to_string(), "bold & !(italic | mixed)");
+//! ```
+
+use crate::MsoTriState;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{BitAnd, BitOr, BitXor, Not, Range};
+use core::str::FromStr;
+
+/// An expression.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expr {
+    /// A literal value.
+    Const(MsoTriState),
+    /// A variable.
+    Var(String),
+    /// `!a`.
+    Not(Box<Expr>),
+    /// `a & b`.
+    And(Box<Expr>, Box<Expr>),
+    /// `a | b`.
+    Or(Box<Expr>, Box<Expr>),
+    /// `a ^ b`.
+    Xor(Box<Expr>, Box<Expr>),
+    /// `a -> b`.
+    Implies(Box<Expr>, Box<Expr>),
+}
+
+/// Variable values for `Expr::eval`.
+pub trait Env {
+    /// Returns the value of a variable, or `None` if it is unbound.
+    fn get(&self, name: &str) -> Option<MsoTriState>;
+}
+
+impl Env for BTreeMap<String, MsoTriState> {
+    fn get(&self, name: &str) -> Option<MsoTriState> {
+        BTreeMap::get(self, name).copied()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: std::hash::BuildHasher> Env for std::collections::HashMap<String, MsoTriState, S> {
+    fn get(&self, name: &str) -> Option<MsoTriState> {
+        std::collections::HashMap::get(self, name).copied()
+    }
+}
+
+impl<F: Fn(&str) -> Option<MsoTriState>> Env for F {
+    fn get(&self, name: &str) -> Option<MsoTriState> {
+        self(name)
+    }
+}
+
+/// The error returned when evaluating an expression with an unbound variable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UnboundVariable(pub String);
+
+impl fmt::Display for UnboundVariable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unbound variable {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnboundVariable {}
+
+impl Expr {
+    /// Creates a variable.
+    pub fn var(name: &str) -> Expr {
+        Expr::Var(name.to_string())
+    }
+
+    /// Creates `self -> rhs`.
+    pub fn implies(self, rhs: Expr) -> Expr {
+        Expr::Implies(Box::new(self), Box::new(rhs))
+    }
+
+    /// Evaluates the expression with Kleene logic.
+    pub fn eval<E: Env + ?Sized>(&self, env: &E) -> Result<MsoTriState, UnboundVariable> {
+        Ok(match self {
+            Expr::Const(m) => m.normalize(),
+            Expr::Var(name) => env
+                .get(name)
+                .ok_or_else(|| UnboundVariable(name.clone()))?
+                .normalize(),
+            Expr::Not(a) => !a.eval(env)?,
+            Expr::And(a, b) => a.eval(env)? & b.eval(env)?,
+            Expr::Or(a, b) => a.eval(env)? | b.eval(env)?,
+            Expr::Xor(a, b) => a.eval(env)? ^ b.eval(env)?,
+            Expr::Implies(a, b) => !a.eval(env)? | b.eval(env)?,
+        })
+    }
+
+    /// Returns the variables in the expression, sorted and without duplicates.
+    pub fn variables(&self) -> Vec<&str> {
+        fn visit<'a>(e: &'a Expr, out: &mut Vec<&'a str>) {
+            match e {
+                Expr::Const(_) => {}
+                Expr::Var(name) => out.push(name),
+                Expr::Not(a) => visit(a, out),
+                Expr::And(a, b) | Expr::Or(a, b) | Expr::Xor(a, b) | Expr::Implies(a, b) => {
+                    visit(a, out);
+                    visit(b, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        visit(self, &mut out);
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Returns the binding strength of the expression's outermost operator.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Implies(..) => 1,
+            Expr::Or(..) => 2,
+            Expr::Xor(..) => 3,
+            Expr::And(..) => 4,
+            Expr::Not(_) => 5,
+            Expr::Const(_) | Expr::Var(_) => 6,
+        }
+    }
+}
+
+impl Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+impl BitAnd for Expr {
+    type Output = Expr;
+
+    fn bitand(self, rhs: Expr) -> Expr {
+        Expr::And(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl BitOr for Expr {
+    type Output = Expr;
+
+    fn bitor(self, rhs: Expr) -> Expr {
+        Expr::Or(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl BitXor for Expr {
+    type Output = Expr;
+
+    fn bitxor(self, rhs: Expr) -> Expr {
+        Expr::Xor(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl From<MsoTriState> for Expr {
+    fn from(m: MsoTriState) -> Expr {
+        Expr::Const(m)
+    }
+}
+
+/// Writes `e`, parenthesised if it binds less tightly than `min`.
+fn fmt_operand(e: &Expr, min: u8, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if e.precedence() < min {
+        write!(f, "({})", e)
+    } else {
+        write!(f, "{}", e)
+    }
+}
+
+/// Prints the expression in the syntax accepted by `FromStr`, with only the parentheses needed
+/// to parse it back to the same tree.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = self.precedence();
+        let (a, op, b) = match self {
+            Expr::Const(m) => return write!(f, "{}", m),
+            Expr::Var(name) => return write!(f, "{}", name),
+            Expr::Not(a) => {
+                write!(f, "!")?;
+                return fmt_operand(a, p, f);
+            }
+            Expr::And(a, b) => (a, "&", b),
+            Expr::Or(a, b) => (a, "|", b),
+            Expr::Xor(a, b) => (a, "^", b),
+            Expr::Implies(a, b) => {
+                // Right associative.
+                fmt_operand(a, p + 1, f)?;
+                write!(f, " -> ")?;
+                return fmt_operand(b, p, f);
+            }
+        };
+        // Left associative.
+        fmt_operand(a, p, f)?;
+        write!(f, " {} ", op)?;
+        fmt_operand(b, p + 1, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Not,
+    And,
+    Or,
+    Xor,
+    Implies,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Not => write!(f, "!"),
+            Token::And => write!(f, "&"),
+            Token::Or => write!(f, "|"),
+            Token::Xor => write!(f, "^"),
+            Token::Implies => write!(f, "->"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+        }
+    }
+}
+
+/// The error returned when parsing an expression fails.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyntaxError {
+    /// The byte range of the input at which parsing failed.
+    pub span: Range<usize>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SyntaxError {}
+
+fn lex(s: &str) -> Result<Vec<(Token, Range<usize>)>, SyntaxError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '!' => Token::Not,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '^' => Token::Xor,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '-' if chars.peek().map(|&(_, c)| c) == Some('>') => {
+                chars.next();
+                Token::Implies
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                Token::Ident(s[start..end].to_string())
+            }
+            c => {
+                return Err(SyntaxError {
+                    span: start..start + c.len_utf8(),
+                    message: alloc::format!("unexpected character {:?}", c),
+                })
+            }
+        };
+        let end = chars.peek().map_or(s.len(), |&(i, _)| i);
+        tokens.push((token, start..end));
+    }
+    Ok(tokens)
+}
+
+/// The deepest expression `FromStr` accepts, counting operators from the root to a leaf.
+///
+/// Parsing, evaluating, printing and simplifying are all recursive, so this keeps untrusted
+/// input from overflowing the stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// An expression and its depth.
+type Parsed = (Expr, usize);
+
+struct Parser {
+    tokens: Vec<(Token, Range<usize>)>,
+    pos: usize,
+    len: usize,
+    /// The number of unclosed parentheses and operators the parser is inside.
+    nesting: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the span of the token just eaten.
+    fn last_span(&self) -> Range<usize> {
+        self.tokens[self.pos - 1].1.clone()
+    }
+
+    fn error(&self, expected: &str) -> SyntaxError {
+        match self.tokens.get(self.pos) {
+            Some((t, span)) => SyntaxError {
+                span: span.clone(),
+                message: alloc::format!("expected {}, found {}", expected, t),
+            },
+            None => SyntaxError {
+                span: self.len..self.len,
+                message: alloc::format!("expected {}, found end of input", expected),
+            },
+        }
+    }
+
+    fn too_deep(span: Range<usize>) -> SyntaxError {
+        SyntaxError {
+            span,
+            message: alloc::format!("expression nested more than {} deep", MAX_DEPTH),
+        }
+    }
+
+    /// Parses `f` one level further in, failing if that is too deep to recurse into.
+    fn nested<T>(
+        &mut self,
+        f: fn(&mut Parser) -> Result<T, SyntaxError>,
+    ) -> Result<T, SyntaxError> {
+        if self.nesting == MAX_DEPTH {
+            return Err(Parser::too_deep(self.last_span()));
+        }
+        self.nesting += 1;
+        let result = f(self);
+        self.nesting -= 1;
+        result
+    }
+
+    /// Builds a binary node from its operands, failing if it is too deep.
+    fn node(span: Range<usize>, e: Expr, a: usize, b: usize) -> Result<Parsed, SyntaxError> {
+        let depth = a.max(b) + 1;
+        if depth > MAX_DEPTH {
+            return Err(Parser::too_deep(span));
+        }
+        Ok((e, depth))
+    }
+
+    fn expr(&mut self) -> Result<Parsed, SyntaxError> {
+        let (lhs, a) = self.binary(0)?;
+        if self.eat(&Token::Implies) {
+            let span = self.last_span();
+            let (rhs, b) = self.nested(Parser::expr)?;
+            Parser::node(span, lhs.implies(rhs), a, b)
+        } else {
+            Ok((lhs, a))
+        }
+    }
+
+    /// Parses the left-associative operators, from `|` at level 0 to `&` at level 2.
+    fn binary(&mut self, level: usize) -> Result<Parsed, SyntaxError> {
+        const LEVELS: [Token; 3] = [Token::Or, Token::Xor, Token::And];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let (mut lhs, mut depth) = self.binary(level + 1)?;
+        while self.eat(&LEVELS[level]) {
+            let span = self.last_span();
+            let (rhs, b) = self.binary(level + 1)?;
+            let e = match level {
+                0 => lhs | rhs,
+                1 => lhs ^ rhs,
+                _ => lhs & rhs,
+            };
+            let (e, d) = Parser::node(span, e, depth, b)?;
+            lhs = e;
+            depth = d;
+        }
+        Ok((lhs, depth))
+    }
+
+    fn unary(&mut self) -> Result<Parsed, SyntaxError> {
+        match self.peek().cloned() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                let span = self.last_span();
+                let (e, depth) = self.nested(Parser::unary)?;
+                Parser::node(span, !e, depth, 0)
+            }
+            Some(Token::Open) => {
+                self.pos += 1;
+                let e = self.nested(Parser::expr)?;
+                if !self.eat(&Token::Close) {
+                    return Err(self.error("`)`"));
+                }
+                Ok(e)
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                let e = match MsoTriState::ALL.iter().find(|m| m.name() == name) {
+                    Some(&m) => Expr::Const(m),
+                    None => Expr::Var(name),
+                };
+                Ok((e, 0))
+            }
+            _ => Err(self.error("an operand")),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = SyntaxError;
+
+    fn from_str(s: &str) -> Result<Expr, SyntaxError> {
+        let mut parser = Parser {
+            tokens: lex(s)?,
+            pos: 0,
+            len: s.len(),
+            nesting: 0,
+        };
+        let (e, _) = parser.expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.error("an operator"));
+        }
+        Ok(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    fn parse(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    fn v(name: &str) -> Expr {
+        Expr::var(name)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(parse("a | b & c"), v("a") | (v("b") & v("c")));
+        assert_eq!(parse("a ^ b | c"), (v("a") ^ v("b")) | v("c"));
+        assert_eq!(parse("a & b ^ c"), (v("a") & v("b")) ^ v("c"));
+        assert_eq!(parse("!a & b"), !v("a") & v("b"));
+        assert_eq!(parse("a -> b -> c"), v("a").implies(v("b").implies(v("c"))));
+        assert_eq!(parse("a | b -> c"), (v("a") | v("b")).implies(v("c")));
+        assert_eq!(parse("a & b & c"), (v("a") & v("b")) & v("c"));
+        assert_eq!(parse("!!msoTrue"), !!Expr::Const(msoTrue));
+        assert_eq!(parse("(a)"), v("a"));
+        assert_eq!(parse("msoTriStateMixed"), Expr::Const(msoTriStateMixed));
+        assert_eq!(parse("mixed"), v("mixed"));
+    }
+
+    #[test]
+    fn display() {
+        for &s in &[
+            "a & b",
+            "a | b & c",
+            "(a | b) & c",
+            "a & (b & c)",
+            "a & b & c",
+            "a -> b -> c",
+            "(a -> b) -> c",
+            "!(a ^ b) -> msoCTrue",
+            "!!a",
+            "a ^ (b | c) ^ d",
+        ] {
+            assert_eq!(parse(s).to_string(), s);
+        }
+        assert_eq!(parse("((a) & (((b))))").to_string(), "a & b");
+    }
+
+    #[test]
+    fn round_trip() {
+        // Every tree of depth 3 over two leaves and all operators prints and parses back.
+        let mut exprs = vec![v("a"), Expr::Const(msoTriStateToggle)];
+        for _ in 0..2 {
+            let mut next = exprs.clone();
+            for a in &exprs {
+                next.push(!a.clone());
+                for b in &exprs {
+                    next.push(a.clone() & b.clone());
+                    next.push(a.clone() | b.clone());
+                    next.push(a.clone() ^ b.clone());
+                    next.push(a.clone().implies(b.clone()));
+                }
+            }
+            exprs = next;
+        }
+        for e in exprs {
+            assert_eq!(parse(&e.to_string()), e, "{}", e);
+        }
+    }
+
+    #[test]
+    fn eval() {
+        let env = |name: &str| match name {
+            "t" => Some(msoTrue),
+            "f" => Some(msoFalse),
+            "u" => Some(msoTriStateMixed),
+            "c" => Some(msoCTrue),
+            _ => None,
+        };
+        assert_eq!(parse("t & u").eval(&env), Ok(msoTriStateMixed));
+        assert_eq!(parse("f & u").eval(&env), Ok(msoFalse));
+        assert_eq!(parse("t | u").eval(&env), Ok(msoTrue));
+        assert_eq!(parse("t ^ c").eval(&env), Ok(msoFalse));
+        assert_eq!(parse("f -> u").eval(&env), Ok(msoTrue));
+        assert_eq!(parse("u -> u").eval(&env), Ok(msoTriStateMixed));
+        assert_eq!(parse("!msoTriStateToggle").eval(&env), Ok(msoTriStateMixed));
+        assert_eq!(
+            parse("t & x").eval(&env),
+            Err(UnboundVariable("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(parse("b & a | !b -> msoTrue").variables(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |s: &str| s.parse::<Expr>().unwrap_err();
+        assert_eq!(error("a & ").span, 4..4);
+        assert_eq!(
+            error("a & ").message,
+            "expected an operand, found end of input"
+        );
+        assert_eq!(error("a # b").span, 2..3);
+        assert_eq!(error("(a | b").span, 6..6);
+        assert_eq!(error("a b").span, 2..3);
+        assert_eq!(error("a b").message, "expected an operator, found b");
+        assert_eq!(error("a - b").span, 2..3);
+        assert_eq!(
+            error("a & )").to_string(),
+            "expected an operand, found ) at 4..5"
+        );
+        assert_eq!(error("").span, 0..0);
+
+        let deep = |open: &str, close: &str, n: usize| {
+            let mut s = open.repeat(n);
+            s.push('a');
+            s.push_str(&close.repeat(n));
+            s
+        };
+        assert!(deep("(", ")", MAX_DEPTH).parse::<Expr>().is_ok());
+        assert!(deep("!", "", MAX_DEPTH).parse::<Expr>().is_ok());
+        assert!(deep("!", "", MAX_DEPTH + 1).parse::<Expr>().is_err());
+        assert!(deep("(", ")", 200_000).parse::<Expr>().is_err());
+        assert!(deep("a -> ", "", 200_000).parse::<Expr>().is_err());
+        assert!(deep("", " & a", MAX_DEPTH).parse::<Expr>().is_ok());
+        let error = deep("", " & a", MAX_DEPTH + 1).parse::<Expr>().unwrap_err();
+        assert_eq!(error.message, "expression nested more than 256 deep");
+        assert_eq!(error.span, 4 * MAX_DEPTH + 2..4 * MAX_DEPTH + 3);
+        assert!(deep("", " & a", 200_000).parse::<Expr>().is_err());
+        assert!(deep("(a & ", ")", 200_000).parse::<Expr>().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! C ABI, declared in `include/mso_tri_state.h`.
+//!
+//! `MsoTriState` is `#[repr(i32)]`, so it is passed by value as a C `int32_t`-sized enum. Values
+//! received from C must be valid `MsoTriState` values; use `mso_tri_state_from_int` to check an
+//! untrusted integer first.
+
+use crate::{MsoTriState, ParseMode};
+use core::convert::TryFrom;
+use core::ffi::{c_char, c_int, CStr};
+
+/// Returned on success.
+pub const MSO_TRI_STATE_OK: c_int = 0;
+/// Returned when a pointer argument is null.
+pub const MSO_TRI_STATE_ERROR_NULL: c_int = -1;
+/// Returned when a value can't be converted or parsed.
+pub const MSO_TRI_STATE_ERROR_INVALID: c_int = -2;
+
+/// Converts an Office integer value, writing it to `out`.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mso_tri_state_from_int(value: i32, out: *mut MsoTriState) -> c_int {
+    if out.is_null() {
+        return MSO_TRI_STATE_ERROR_NULL;
+    }
+    match MsoTriState::from_i32(value) {
+        Ok(m) => {
+            *out = m;
+            MSO_TRI_STATE_OK
+        }
+        Err(_) => MSO_TRI_STATE_ERROR_INVALID,
+    }
+}
+
+/// Converts to the Office integer value.
+#[no_mangle]
+pub extern "C" fn mso_tri_state_to_int(m: MsoTriState) -> i32 {
+    m.to_i32()
+}
+
+/// Converts from a `bool`.
+#[no_mangle]
+pub extern "C" fn mso_tri_state_from_bool(b: bool) -> MsoTriState {
+    b.into()
+}
+
+/// Converts to a `bool`, writing it to `out`.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn mso_tri_state_to_bool(m: MsoTriState, out: *mut bool) -> c_int {
+    if out.is_null() {
+        return MSO_TRI_STATE_ERROR_NULL;
+    }
+    match bool::try_from(m) {
+        Ok(b) => {
+            *out = b;
+            MSO_TRI_STATE_OK
+        }
+        Err(_) => MSO_TRI_STATE_ERROR_INVALID,
+    }
+}
+
+/// Kleene negation.
+#[no_mangle]
+pub extern "C" fn mso_tri_state_not(a: MsoTriState) -> MsoTriState {
+    !a
+}
+
+/// Kleene conjunction.
+#[no_mangle]
+pub extern "C" fn mso_tri_state_and(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+    a & b
+}
+
+/// Kleene disjunction.
+#[no_mangle]
+pub extern "C" fn mso_tri_state_or(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+    a | b
+}
+
+/// Kleene exclusive disjunction.
+#[no_mangle]
+pub extern "C" fn mso_tri_state_xor(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+    a ^ b
+}
+
+/// Writes the Office name of the value into `buf` as a NUL-terminated string, truncating it to
+/// fit `len` bytes.
+///
+/// Returns the length of the name without the NUL, like `snprintf`.
+///
+/// # Safety
+/// `buf` must be null or valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mso_tri_state_to_string(
+    m: MsoTriState,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    let name = m.name().as_bytes();
+    if !buf.is_null() && len > 0 {
+        let n = name.len().min(len - 1);
+        core::ptr::copy_nonoverlapping(name.as_ptr(), buf as *mut u8, n);
+        *buf.add(n) = 0;
+    }
+    name.len()
+}
+
+/// Parses a NUL-terminated string, writing the value to `out`.
+///
+/// Accepts the forms of `ParseMode::Lenient` if `lenient` is true, and of `ParseMode::Strict`
+/// otherwise.
+///
+/// # Safety
+/// `s` must be null or a valid NUL-terminated string, and `out` must be null or valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn mso_tri_state_parse(
+    s: *const c_char,
+    lenient: bool,
+    out: *mut MsoTriState,
+) -> c_int {
+    if s.is_null() || out.is_null() {
+        return MSO_TRI_STATE_ERROR_NULL;
+    }
+    let mode = if lenient {
+        ParseMode::Lenient
+    } else {
+        ParseMode::Strict
+    };
+    let parsed = CStr::from_ptr(s)
+        .to_str()
+        .ok()
+        .and_then(|s| MsoTriState::parse(s, mode).ok());
+    match parsed {
+        Some(m) => {
+            *out = m;
+            MSO_TRI_STATE_OK
+        }
+        None => MSO_TRI_STATE_ERROR_INVALID,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    #[test]
+    fn to_string() {
+        let mut buf = [0x7f as c_char; 8];
+        let n = unsafe { mso_tri_state_to_string(msoTriStateMixed, buf.as_mut_ptr(), buf.len()) };
+        assert_eq!(n, "msoTriStateMixed".len());
+        assert_eq!(
+            unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(),
+            b"msoTriS"
+        );
+        assert_eq!(
+            unsafe { mso_tri_state_to_string(msoTrue, core::ptr::null_mut(), 0) },
+            7
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let mut m = msoFalse;
+        let parse = |s: &[u8], lenient, m: &mut MsoTriState| unsafe {
+            let s = CStr::from_bytes_with_nul(s).unwrap();
+            mso_tri_state_parse(s.as_ptr(), lenient, m)
+        };
+        assert_eq!(parse(b"msoTrue\0", false, &mut m), MSO_TRI_STATE_OK);
+        assert_eq!(m, msoTrue);
+        assert_eq!(
+            parse(b"mixed\0", false, &mut m),
+            MSO_TRI_STATE_ERROR_INVALID
+        );
+        assert_eq!(parse(b"mixed\0", true, &mut m), MSO_TRI_STATE_OK);
+        assert_eq!(m, msoTriStateMixed);
+        assert_eq!(
+            unsafe { mso_tri_state_parse(core::ptr::null(), true, &mut m) },
+            MSO_TRI_STATE_ERROR_NULL
+        );
+    }
+
+    #[test]
+    fn from_int() {
+        let mut m = msoFalse;
+        assert_eq!(
+            unsafe { mso_tri_state_from_int(-3, &mut m) },
+            MSO_TRI_STATE_OK
+        );
+        assert_eq!(m, msoTriStateToggle);
+        assert_eq!(
+            unsafe { mso_tri_state_from_int(5, &mut m) },
+            MSO_TRI_STATE_ERROR_INVALID
+        );
+        assert_eq!(m, msoTriStateToggle);
+    }
+}
@@ -25,10 +25,11 @@
 //! ```
 //! extern crate mso_tri_state;
 //! use mso_tri_state::MsoTriState;
+//! use std::convert::TryInto;
 //!
 //! // Clean and easy to read
 //! let foo = MsoTriState::msoTrue;
-//! if foo.into() {
+//! if foo.try_into().unwrap_or(false) {
 //!     println!("Hello, world!");
 //! }
 //!
@@ -45,17 +46,73 @@
 //! let has_a_3 = MsoTriState::from(vec![1, 2, 4, 5].contains(&3));
 //! println!("Has a 3: {}", has_a_3); // prints "Has a 3: msoFalse"
 //! ```
+//!
+//! ## Features
+//! The crate is `no_std`. Default features can be disabled for embedded targets:
+//! - `alloc` (default): the collections and other types which allocate.
+//! - `std` (default): `std::error::Error` implementations and the `vcd` module.
+//! - `ffi`: the C ABI in the `ffi` module, declared in `include/mso_tri_state.h`.
+//! - `serde`: serde support.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(missing_docs)]
 
-use std::fmt;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod aggregate;
+pub mod caps;
+pub mod command;
+#[cfg(feature = "alloc")]
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod logic;
+#[cfg(feature = "alloc")]
+pub mod mdd;
+#[cfg(feature = "alloc")]
+pub mod office;
+mod ops;
+#[cfg(feature = "alloc")]
+pub mod packed;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "alloc")]
+pub mod sim;
+#[cfg(feature = "alloc")]
+pub mod simplify;
+#[cfg(feature = "alloc")]
+pub mod solve;
+#[cfg(feature = "alloc")]
+pub mod table;
+#[cfg(feature = "alloc")]
+pub mod ternary;
+#[cfg(all(test, feature = "alloc"))]
+pub(crate) mod testutil;
+#[cfg(feature = "alloc")]
+pub mod text;
+#[cfg(feature = "alloc")]
+pub mod tree;
+#[cfg(feature = "std")]
+pub mod vcd;
+#[cfg(feature = "alloc")]
+pub mod vec;
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
 
 /// Specifies a tri-state Boolean value.
-#[derive(Debug, PartialEq)]
+///
+/// Values are ordered by their Office discriminants. The default value is `msoFalse`, for parity
+/// with `bool`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(i32)]
 #[allow(non_snake_case, non_camel_case_types)] // We're better than the compiler
 pub enum MsoTriState {
     /// Not supported.
     msoCTrue = 1,
     /// False.
+    #[default]
     msoFalse = 0,
     /// Not supported.
     msoTriStateMixed = -2,
@@ -75,29 +132,242 @@ impl From<bool> for MsoTriState {
     }
 }
 
-impl From<MsoTriState> for bool {
-    fn from(m: MsoTriState) -> bool {
+impl MsoTriState {
+    /// Every `MsoTriState` value, in declaration order.
+    pub const ALL: [MsoTriState; 5] = [
+        MsoTriState::msoCTrue,
+        MsoTriState::msoFalse,
+        MsoTriState::msoTriStateMixed,
+        MsoTriState::msoTriStateToggle,
+        MsoTriState::msoTrue,
+    ];
+
+    /// The three values of the underlying logic, in truth order, so that
+    /// `CORE[ops::rank(m)]` is `m.normalize()`.
+    pub(crate) const CORE: [MsoTriState; 3] = [
+        MsoTriState::msoFalse,
+        MsoTriState::msoTriStateMixed,
+        MsoTriState::msoTrue,
+    ];
+
+    /// Returns the Office name of the value, as printed by `Display`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            MsoTriState::msoCTrue => "msoCTrue",
+            MsoTriState::msoFalse => "msoFalse",
+            MsoTriState::msoTriStateMixed => "msoTriStateMixed",
+            MsoTriState::msoTriStateToggle => "msoTriStateToggle",
+            MsoTriState::msoTrue => "msoTrue",
+        }
+    }
+
+    /// Parses a value using the given mode.
+    ///
+    /// `FromStr` uses `ParseMode::Strict`.
+    pub fn parse(s: &str, mode: ParseMode) -> Result<MsoTriState, ParseError> {
+        if let Some(&m) = MsoTriState::ALL.iter().find(|m| m.name() == s) {
+            return Ok(m);
+        }
+        if mode == ParseMode::Strict {
+            return Err(ParseError(mode));
+        }
+
+        let s = s.trim();
+        if let Ok(v) = s.parse::<i32>() {
+            return MsoTriState::from_i32(v).map_err(|_| ParseError(mode));
+        }
+        MsoTriState::ALL
+            .iter()
+            .copied()
+            .find(|m| {
+                let name = m.name();
+                let short = name.trim_start_matches("msoTriState");
+                s.eq_ignore_ascii_case(name)
+                    || s.eq_ignore_ascii_case(&name[3..])
+                    || s.eq_ignore_ascii_case(short)
+            })
+            .ok_or(ParseError(mode))
+    }
+
+    /// Maps the value onto the three values used by the logic operators.
+    ///
+    /// `msoCTrue` becomes `msoTrue`, and `msoTriStateToggle`, whose result depends on a state the
+    /// expression can't see, becomes `msoTriStateMixed`. Other values are unchanged.
+    pub const fn normalize(self) -> MsoTriState {
+        match self {
+            MsoTriState::msoCTrue | MsoTriState::msoTrue => MsoTriState::msoTrue,
+            MsoTriState::msoFalse => MsoTriState::msoFalse,
+            MsoTriState::msoTriStateMixed | MsoTriState::msoTriStateToggle => {
+                MsoTriState::msoTriStateMixed
+            }
+        }
+    }
+
+    /// Converts to the Office integer value.
+    pub const fn to_i32(self) -> i32 {
+        self as i32
+    }
+
+    /// Converts from an Office integer value.
+    pub const fn from_i32(v: i32) -> Result<MsoTriState, UnknownValue> {
+        match v {
+            1 => Ok(MsoTriState::msoCTrue),
+            0 => Ok(MsoTriState::msoFalse),
+            -2 => Ok(MsoTriState::msoTriStateMixed),
+            -3 => Ok(MsoTriState::msoTriStateToggle),
+            -1 => Ok(MsoTriState::msoTrue),
+            _ => Err(UnknownValue(v as i64)),
+        }
+    }
+
+    /// Converts to a `bool`, panicking if the value is not supported.
+    ///
+    /// This is the behaviour of the old `From<MsoTriState> for bool` implementation, kept to ease
+    /// migration. Use `bool::try_from` instead.
+    #[deprecated(since = "0.2.0", note = "use `bool::try_from` instead")]
+    pub fn into_bool(self) -> bool {
+        match bool::try_from(self) {
+            Ok(b) => b,
+            Err(_) => panic!("Not supported."),
+        }
+    }
+}
+
+/// The error returned when converting an unsupported `MsoTriState` into a `bool`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NotSupported(pub MsoTriState);
+
+impl fmt::Display for NotSupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: Not supported.", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotSupported {}
+
+/// The error returned when converting an integer which is not an Office value into an
+/// `MsoTriState`.
+///
+/// Unsigned values above `i64::MAX` are reported as `i64::MAX`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnknownValue(pub i64);
+
+impl fmt::Display for UnknownValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: Not a valid MsoTriState.", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownValue {}
+
+macro_rules! impl_int_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl From<MsoTriState> for $t {
+                fn from(m: MsoTriState) -> $t {
+                    m.to_i32() as $t
+                }
+            }
+
+            impl TryFrom<$t> for MsoTriState {
+                type Error = UnknownValue;
+
+                fn try_from(v: $t) -> Result<MsoTriState, UnknownValue> {
+                    match i32::try_from(v) {
+                        Ok(v) => MsoTriState::from_i32(v),
+                        Err(_) => Err(UnknownValue(v as i64)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_int_conversions!(i8, i16, i32, i64, isize);
+
+// Only `msoFalse` and `msoCTrue` are non-negative, so there is no `From<MsoTriState>` for unsigned
+// types.
+macro_rules! impl_unsigned_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<$t> for MsoTriState {
+                type Error = UnknownValue;
+
+                fn try_from(v: $t) -> Result<MsoTriState, UnknownValue> {
+                    let v = v as u64;
+                    match i32::try_from(v) {
+                        Ok(v) => MsoTriState::from_i32(v),
+                        Err(_) => Err(UnknownValue(i64::try_from(v).unwrap_or(i64::MAX))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned_conversions!(u8, u16, u32, u64, usize);
+
+impl TryFrom<MsoTriState> for bool {
+    type Error = NotSupported;
+
+    fn try_from(m: MsoTriState) -> Result<bool, NotSupported> {
         match m {
-            MsoTriState::msoFalse => false,
-            MsoTriState::msoTrue => true,
-            _ => panic!("Not supported."),
+            MsoTriState::msoFalse => Ok(false),
+            MsoTriState::msoTrue => Ok(true),
+            _ => Err(NotSupported(m)),
         }
     }
 }
 
 impl fmt::Display for MsoTriState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                MsoTriState::msoCTrue => "msoCTrue",
-                MsoTriState::msoFalse => "msoFalse",
-                MsoTriState::msoTriStateMixed => "msoTriStateMixed",
-                MsoTriState::msoTriStateToggle => "msoTriStateToggle",
-                MsoTriState::msoTrue => "msoTrue",
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How strictly `MsoTriState::parse` accepts its input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// Only the Office names printed by `Display`, e.g. `msoTriStateMixed`.
+    Strict,
+    /// Also accepts any case, the names without their `mso` or `msoTriState` prefix (`True`,
+    /// `mixed`, `toggle`) and the Office integer values (`-1`).
+    Lenient,
+}
+
+/// The error returned when parsing an `MsoTriState` fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParseError(pub ParseMode);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected one of ")?;
+        for (i, m) in MsoTriState::ALL.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
             }
-        )
+            write!(f, "{}", m)?;
+        }
+        if self.0 == ParseMode::Lenient {
+            write!(
+                f,
+                " (in any case, optionally without the mso or msoTriState prefix) or one of 1, 0, -2, -3, -1"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl FromStr for MsoTriState {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<MsoTriState, ParseError> {
+        MsoTriState::parse(s, ParseMode::Strict)
     }
 }
 
@@ -113,12 +383,178 @@ mod tests {
 
     #[test]
     fn mso_tri_state_to_bool() {
-        assert_eq!(bool::from(MsoTriState::msoFalse), false);
-        assert_eq!(bool::from(MsoTriState::msoTrue), true);
+        assert_eq!(bool::try_from(MsoTriState::msoFalse), Ok(false));
+        assert_eq!(bool::try_from(MsoTriState::msoTrue), Ok(true));
+
+        assert_eq!(
+            bool::try_from(MsoTriState::msoCTrue),
+            Err(NotSupported(MsoTriState::msoCTrue))
+        );
+        assert_eq!(
+            bool::try_from(MsoTriState::msoTriStateMixed),
+            Err(NotSupported(MsoTriState::msoTriStateMixed))
+        );
+        assert_eq!(
+            bool::try_from(MsoTriState::msoTriStateToggle),
+            Err(NotSupported(MsoTriState::msoTriStateToggle))
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn mso_tri_state_into_bool() {
+        assert!(MsoTriState::msoTrue.into_bool());
+        std::panic::catch_unwind(|| MsoTriState::msoTriStateMixed.into_bool()).unwrap_err();
+    }
+
+    #[test]
+    fn not_supported_display() {
+        assert_eq!(
+            NotSupported(MsoTriState::msoTriStateMixed).to_string(),
+            "msoTriStateMixed: Not supported."
+        );
+    }
 
-        std::panic::catch_unwind(|| bool::from(MsoTriState::msoCTrue)).unwrap_err();
-        std::panic::catch_unwind(|| bool::from(MsoTriState::msoTriStateMixed)).unwrap_err();
-        std::panic::catch_unwind(|| bool::from(MsoTriState::msoTriStateToggle)).unwrap_err();
+    #[test]
+    fn int_conversions() {
+        for &m in &[
+            MsoTriState::msoCTrue,
+            MsoTriState::msoFalse,
+            MsoTriState::msoTriStateMixed,
+            MsoTriState::msoTriStateToggle,
+            MsoTriState::msoTrue,
+        ] {
+            assert_eq!(MsoTriState::try_from(i8::from(m)), Ok(m));
+            assert_eq!(MsoTriState::try_from(i16::from(m)), Ok(m));
+            assert_eq!(MsoTriState::try_from(i32::from(m)), Ok(m));
+            assert_eq!(MsoTriState::try_from(i64::from(m)), Ok(m));
+            assert_eq!(MsoTriState::try_from(isize::from(m)), Ok(m));
+        }
+
+        assert_eq!(MsoTriState::try_from(2i32), Err(UnknownValue(2)));
+        assert_eq!(MsoTriState::try_from(-4i8), Err(UnknownValue(-4)));
+        assert_eq!(
+            MsoTriState::try_from(i64::MAX - 1),
+            Err(UnknownValue(i64::MAX - 1))
+        );
+        assert_eq!(
+            MsoTriState::try_from((1i64 << 32) - 1),
+            Err(UnknownValue((1i64 << 32) - 1))
+        );
+    }
+
+    #[test]
+    fn unsigned_conversions() {
+        assert_eq!(MsoTriState::try_from(0u8), Ok(MsoTriState::msoFalse));
+        assert_eq!(MsoTriState::try_from(1u16), Ok(MsoTriState::msoCTrue));
+        assert_eq!(MsoTriState::try_from(1u32), Ok(MsoTriState::msoCTrue));
+        assert_eq!(MsoTriState::try_from(0usize), Ok(MsoTriState::msoFalse));
+        assert_eq!(MsoTriState::try_from(2u8), Err(UnknownValue(2)));
+        // Not -1 reinterpreted as `msoTrue`.
+        assert_eq!(
+            MsoTriState::try_from(u32::MAX),
+            Err(UnknownValue(u32::MAX as i64))
+        );
+        assert_eq!(MsoTriState::try_from(u64::MAX), Err(UnknownValue(i64::MAX)));
+    }
+
+    #[test]
+    fn const_int_conversions() {
+        const TRUE: i32 = MsoTriState::msoTrue.to_i32();
+        const MIXED: Result<MsoTriState, UnknownValue> = MsoTriState::from_i32(-2);
+        assert_eq!(TRUE, -1);
+        assert_eq!(MIXED, Ok(MsoTriState::msoTriStateMixed));
+        assert_eq!(MsoTriState::from_i32(7), Err(UnknownValue(7)));
+    }
+
+    #[test]
+    fn parse_strict() {
+        for &m in &MsoTriState::ALL {
+            assert_eq!(m.to_string().parse(), Ok(m));
+        }
+
+        assert_eq!(
+            "msotrue".parse::<MsoTriState>(),
+            Err(ParseError(ParseMode::Strict))
+        );
+        assert_eq!(
+            "-1".parse::<MsoTriState>(),
+            Err(ParseError(ParseMode::Strict))
+        );
+    }
+
+    #[test]
+    fn parse_lenient() {
+        fn parse(s: &str) -> Result<MsoTriState, ParseError> {
+            MsoTriState::parse(s, ParseMode::Lenient)
+        }
+
+        for &m in &MsoTriState::ALL {
+            assert_eq!(parse(&m.to_string()), Ok(m));
+            assert_eq!(parse(&m.to_i32().to_string()), Ok(m));
+        }
+
+        assert_eq!(parse("True"), Ok(MsoTriState::msoTrue));
+        assert_eq!(parse(" FALSE "), Ok(MsoTriState::msoFalse));
+        assert_eq!(parse("ctrue"), Ok(MsoTriState::msoCTrue));
+        assert_eq!(parse("mixed"), Ok(MsoTriState::msoTriStateMixed));
+        assert_eq!(parse("TriStateToggle"), Ok(MsoTriState::msoTriStateToggle));
+        assert_eq!(parse("toggle"), Ok(MsoTriState::msoTriStateToggle));
+        assert_eq!(parse("MSOTRUE"), Ok(MsoTriState::msoTrue));
+
+        assert_eq!(parse("2"), Err(ParseError(ParseMode::Lenient)));
+        assert_eq!(parse("yes"), Err(ParseError(ParseMode::Lenient)));
+        assert_eq!(parse(""), Err(ParseError(ParseMode::Lenient)));
+    }
+
+    #[test]
+    fn parse_error_display() {
+        assert_eq!(
+            ParseError(ParseMode::Strict).to_string(),
+            "expected one of msoCTrue, msoFalse, msoTriStateMixed, msoTriStateToggle, msoTrue"
+        );
+        assert!(ParseError(ParseMode::Lenient)
+            .to_string()
+            .ends_with("or one of 1, 0, -2, -3, -1"));
+    }
+
+    #[test]
+    fn default() {
+        assert_eq!(MsoTriState::default(), MsoTriState::msoFalse);
+    }
+
+    #[test]
+    fn repr() {
+        assert_eq!(std::mem::size_of::<MsoTriState>(), 4);
+        assert_eq!(MsoTriState::msoCTrue as i32, 1);
+        assert_eq!(MsoTriState::msoFalse as i32, 0);
+        assert_eq!(MsoTriState::msoTriStateMixed as i32, -2);
+        assert_eq!(MsoTriState::msoTriStateToggle as i32, -3);
+        assert_eq!(MsoTriState::msoTrue as i32, -1);
+    }
+
+    #[test]
+    fn ordering() {
+        let set: std::collections::BTreeSet<_> = [
+            MsoTriState::msoCTrue,
+            MsoTriState::msoFalse,
+            MsoTriState::msoTrue,
+            MsoTriState::msoTriStateToggle,
+            MsoTriState::msoTriStateMixed,
+        ]
+        .iter()
+        .copied()
+        .collect();
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![
+                MsoTriState::msoTriStateToggle,
+                MsoTriState::msoTriStateMixed,
+                MsoTriState::msoTrue,
+                MsoTriState::msoFalse,
+                MsoTriState::msoCTrue,
+            ]
+        );
     }
 
     #[test]
@@ -0,0 +1,214 @@
+//! Selectable three-valued logic systems.
+//!
+//! Each system is a marker type implementing `Logic`, so values can be combined under a chosen
+//! semantics:
+//!
+//! ```
+//! use mso_tri_state::logic::{Kleene, Logic, Lukasiewicz};
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! assert_eq!(Kleene::implies(msoTriStateMixed, msoTriStateMixed), msoTriStateMixed);
+//! assert_eq!(Lukasiewicz::implies(msoTriStateMixed, msoTriStateMixed), msoTrue);
+//! ```
+//!
+//! As with the operators on `MsoTriState`, operands are normalized first, so `msoCTrue` acts as
+//! `msoTrue` and `msoTriStateToggle` as `msoTriStateMixed`.
+
+use crate::MsoTriState;
+
+/// A three-valued logic, with `msoTriStateMixed` as its third value.
+///
+/// The provided methods implement strong Kleene logic with material implication.
+pub trait Logic {
+    /// Returns whether the value counts as true for the purposes of validity.
+    fn is_designated(a: MsoTriState) -> bool;
+
+    /// Negation.
+    fn not(a: MsoTriState) -> MsoTriState {
+        !a
+    }
+
+    /// Conjunction.
+    fn and(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        a & b
+    }
+
+    /// Disjunction.
+    fn or(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        a | b
+    }
+
+    /// Implication.
+    fn implies(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        Self::or(Self::not(a), b)
+    }
+
+    /// Equivalence.
+    fn equiv(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        Self::and(Self::implies(a, b), Self::implies(b, a))
+    }
+}
+
+/// Strong Kleene logic: `msoTriStateMixed` is unknown. Only `msoTrue` is designated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Kleene;
+
+impl Logic for Kleene {
+    fn is_designated(a: MsoTriState) -> bool {
+        a.normalize() == MsoTriState::msoTrue
+    }
+}
+
+/// Łukasiewicz logic: as Kleene, except that unknown implies unknown. Only `msoTrue` is
+/// designated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Lukasiewicz;
+
+impl Logic for Lukasiewicz {
+    fn is_designated(a: MsoTriState) -> bool {
+        a.normalize() == MsoTriState::msoTrue
+    }
+
+    fn implies(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        if a.normalize() == MsoTriState::msoTriStateMixed
+            && b.normalize() == MsoTriState::msoTriStateMixed
+        {
+            MsoTriState::msoTrue
+        } else {
+            !a | b
+        }
+    }
+}
+
+/// Bochvar (weak Kleene) logic: `msoTriStateMixed` is nonsense and infects every result. Only
+/// `msoTrue` is designated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bochvar;
+
+impl Bochvar {
+    fn weak(
+        a: MsoTriState,
+        b: MsoTriState,
+        f: fn(MsoTriState, MsoTriState) -> MsoTriState,
+    ) -> MsoTriState {
+        let (a, b) = (a.normalize(), b.normalize());
+        if a == MsoTriState::msoTriStateMixed || b == MsoTriState::msoTriStateMixed {
+            MsoTriState::msoTriStateMixed
+        } else {
+            f(a, b)
+        }
+    }
+}
+
+impl Logic for Bochvar {
+    fn is_designated(a: MsoTriState) -> bool {
+        a.normalize() == MsoTriState::msoTrue
+    }
+
+    fn and(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        Bochvar::weak(a, b, |a, b| a & b)
+    }
+
+    fn or(a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        Bochvar::weak(a, b, |a, b| a | b)
+    }
+}
+
+/// Priest's logic of paradox: `msoTriStateMixed` is both true and false. The connectives are
+/// Kleene's, but both `msoTrue` and `msoTriStateMixed` are designated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Priest;
+
+impl Logic for Priest {
+    fn is_designated(a: MsoTriState) -> bool {
+        a.normalize() != MsoTriState::msoFalse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    fn value(c: char) -> MsoTriState {
+        match c {
+            'F' => msoFalse,
+            'U' => msoTriStateMixed,
+            'T' => msoTrue,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Checks a binary connective against a table of `F`, `U` and `T` with rows and columns in
+    /// the order of `MsoTriState::CORE`.
+    fn check(f: fn(MsoTriState, MsoTriState) -> MsoTriState, table: [&str; 3]) {
+        for (i, &a) in MsoTriState::CORE.iter().enumerate() {
+            for (j, (&b, c)) in MsoTriState::CORE.iter().zip(table[i].chars()).enumerate() {
+                assert_eq!(f(a, b), value(c), "row {} column {}", i, j);
+            }
+        }
+    }
+
+    fn check_not<L: Logic>() {
+        assert_eq!(L::not(msoFalse), msoTrue);
+        assert_eq!(L::not(msoTriStateMixed), msoTriStateMixed);
+        assert_eq!(L::not(msoTrue), msoFalse);
+    }
+
+    #[test]
+    fn kleene() {
+        check_not::<Kleene>();
+        check(Kleene::and, ["FFF", "FUU", "FUT"]);
+        check(Kleene::or, ["FUT", "UUT", "TTT"]);
+        check(Kleene::implies, ["TTT", "UUT", "FUT"]);
+        check(Kleene::equiv, ["TUF", "UUU", "FUT"]);
+    }
+
+    #[test]
+    fn lukasiewicz() {
+        check_not::<Lukasiewicz>();
+        check(Lukasiewicz::and, ["FFF", "FUU", "FUT"]);
+        check(Lukasiewicz::or, ["FUT", "UUT", "TTT"]);
+        check(Lukasiewicz::implies, ["TTT", "UTT", "FUT"]);
+        check(Lukasiewicz::equiv, ["TUF", "UTU", "FUT"]);
+    }
+
+    #[test]
+    fn bochvar() {
+        check_not::<Bochvar>();
+        check(Bochvar::and, ["FUF", "UUU", "FUT"]);
+        check(Bochvar::or, ["FUT", "UUU", "TUT"]);
+        check(Bochvar::implies, ["TUT", "UUU", "FUT"]);
+        check(Bochvar::equiv, ["TUF", "UUU", "FUT"]);
+    }
+
+    #[test]
+    fn priest() {
+        check_not::<Priest>();
+        check(Priest::and, ["FFF", "FUU", "FUT"]);
+        check(Priest::or, ["FUT", "UUT", "TTT"]);
+        check(Priest::implies, ["TTT", "UUT", "FUT"]);
+        check(Priest::equiv, ["TUF", "UUU", "FUT"]);
+    }
+
+    #[test]
+    fn designated() {
+        for &m in &MsoTriState::ALL {
+            let truthy = m.normalize() == msoTrue;
+            assert_eq!(Kleene::is_designated(m), truthy);
+            assert_eq!(Lukasiewicz::is_designated(m), truthy);
+            assert_eq!(Bochvar::is_designated(m), truthy);
+            assert_eq!(Priest::is_designated(m), m.normalize() != msoFalse);
+        }
+    }
+
+    #[test]
+    fn normalized_operands() {
+        assert_eq!(
+            Lukasiewicz::implies(msoTriStateToggle, msoTriStateMixed),
+            msoTrue
+        );
+        assert_eq!(Bochvar::or(msoCTrue, msoTriStateToggle), msoTriStateMixed);
+        assert_eq!(Kleene::and(msoCTrue, msoTrue), msoTrue);
+    }
+}
@@ -0,0 +1,533 @@
+//! Reduced ordered multi-valued decision diagrams over `MsoTriState` variables.
+//!
+//! Each node branches three ways on one variable: `msoFalse`, `msoTriStateMixed` and `msoTrue`.
+//! Variables are tested in the order they were added to the `Store`, and nodes are hash-consed
+//! there, so two functions are equal exactly when their `Node`s are.
+//!
+//! ```
+//! use mso_tri_state::mdd::Store;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let mut store = Store::new();
+//! let a = store.build(&"a -> b".parse().unwrap());
+//! let b = store.build(&"!b -> !a".parse().unwrap());
+//! assert_eq!(a, b);
+//!
+//! // Of the nine assignments to `a` and `b`, five make `a -> b` true.
+//! assert_eq!(store.count(a, msoTrue), Some(5));
+//! assert_eq!(store.count(a, msoTriStateMixed), Some(3));
+//! assert_eq!(store.count(a, msoFalse), Some(1));
+//! ```
+
+use crate::expr::{Env, Expr, UnboundVariable};
+use crate::ops::rank;
+use crate::MsoTriState::{self, *};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Returns the index of a node's child for a value, in the order of `MsoTriState::CORE`.
+fn branch(m: MsoTriState) -> usize {
+    rank(m) as usize
+}
+
+/// A function in a `Store`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Node(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Entry {
+    Terminal(MsoTriState),
+    Branch { level: usize, children: [Node; 3] },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Op {
+    And,
+    Or,
+    Xor,
+    Implies,
+}
+
+impl Op {
+    fn eval(self, a: MsoTriState, b: MsoTriState) -> MsoTriState {
+        match self {
+            Op::And => a & b,
+            Op::Or => a | b,
+            Op::Xor => a ^ b,
+            Op::Implies => !a | b,
+        }
+    }
+}
+
+/// The nodes of a set of diagrams that share a variable order.
+#[derive(Clone, Debug)]
+pub struct Store {
+    entries: Vec<Entry>,
+    unique: BTreeMap<Entry, Node>,
+    vars: Vec<String>,
+    levels: BTreeMap<String, usize>,
+    not_cache: BTreeMap<Node, Node>,
+    apply_cache: BTreeMap<(Op, Node, Node), Node>,
+}
+
+impl Default for Store {
+    fn default() -> Store {
+        Store::new()
+    }
+}
+
+impl Store {
+    /// Creates a store with the three constant functions and no variables.
+    pub fn new() -> Store {
+        let mut store = Store {
+            entries: Vec::new(),
+            unique: BTreeMap::new(),
+            vars: Vec::new(),
+            levels: BTreeMap::new(),
+            not_cache: BTreeMap::new(),
+            apply_cache: BTreeMap::new(),
+        };
+        for &m in &MsoTriState::CORE {
+            store.intern(Entry::Terminal(m));
+        }
+        store
+    }
+
+    fn intern(&mut self, entry: Entry) -> Node {
+        if let Some(&node) = self.unique.get(&entry) {
+            return node;
+        }
+        let node = Node(self.entries.len());
+        self.entries.push(entry);
+        self.unique.insert(entry, node);
+        node
+    }
+
+    /// Returns the node testing the variable at `level`, or the shared child if all three are
+    /// the same.
+    fn branch(&mut self, level: usize, children: [Node; 3]) -> Node {
+        if children[0] == children[1] && children[1] == children[2] {
+            children[0]
+        } else {
+            self.intern(Entry::Branch { level, children })
+        }
+    }
+
+    fn level(&self, node: Node) -> usize {
+        match self.entries[node.0] {
+            Entry::Terminal(_) => usize::MAX,
+            Entry::Branch { level, .. } => level,
+        }
+    }
+
+    /// Returns the children of `node` when branching on the variable at `level`.
+    fn cofactors(&self, node: Node, level: usize) -> [Node; 3] {
+        match self.entries[node.0] {
+            Entry::Branch { level: l, children } if l == level => children,
+            _ => [node; 3],
+        }
+    }
+
+    /// Returns the variables, in the order they are tested.
+    pub fn variables(&self) -> &[String] {
+        &self.vars
+    }
+
+    /// Returns the constant function.
+    pub fn constant(&self, m: MsoTriState) -> Node {
+        Node(branch(m))
+    }
+
+    /// Returns the value of `node` if it is a constant function.
+    pub fn as_constant(&self, node: Node) -> Option<MsoTriState> {
+        match self.entries[node.0] {
+            Entry::Terminal(m) => Some(m),
+            Entry::Branch { .. } => None,
+        }
+    }
+
+    /// Returns the function which is the value of a variable, adding the variable after the
+    /// existing ones if it is new.
+    pub fn var(&mut self, name: &str) -> Node {
+        let level = match self.levels.get(name) {
+            Some(&level) => level,
+            None => {
+                self.vars.push(name.to_string());
+                self.levels.insert(name.to_string(), self.vars.len() - 1);
+                self.vars.len() - 1
+            }
+        };
+        let leaves = [Node(0), Node(1), Node(2)];
+        self.branch(level, leaves)
+    }
+
+    /// Returns `!a`.
+    pub fn not(&mut self, a: Node) -> Node {
+        let children = match self.entries[a.0] {
+            Entry::Terminal(m) => return self.constant(!m),
+            Entry::Branch { children, .. } => children,
+        };
+        if let Some(&node) = self.not_cache.get(&a) {
+            return node;
+        }
+        let level = self.level(a);
+        let children = [
+            self.not(children[0]),
+            self.not(children[1]),
+            self.not(children[2]),
+        ];
+        let node = self.branch(level, children);
+        self.not_cache.insert(a, node);
+        node
+    }
+
+    fn apply(&mut self, op: Op, a: Node, b: Node) -> Node {
+        if let (Some(x), Some(y)) = (self.as_constant(a), self.as_constant(b)) {
+            return self.constant(op.eval(x, y));
+        }
+        if let Some(&node) = self.apply_cache.get(&(op, a, b)) {
+            return node;
+        }
+        let level = self.level(a).min(self.level(b));
+        let (x, y) = (self.cofactors(a, level), self.cofactors(b, level));
+        let children = [
+            self.apply(op, x[0], y[0]),
+            self.apply(op, x[1], y[1]),
+            self.apply(op, x[2], y[2]),
+        ];
+        let node = self.branch(level, children);
+        self.apply_cache.insert((op, a, b), node);
+        node
+    }
+
+    /// Returns `a & b`.
+    pub fn and(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::And, a, b)
+    }
+
+    /// Returns `a | b`.
+    pub fn or(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::Or, a, b)
+    }
+
+    /// Returns `a ^ b`.
+    pub fn xor(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::Xor, a, b)
+    }
+
+    /// Returns `a -> b`, that is `!a | b`.
+    pub fn implies(&mut self, a: Node, b: Node) -> Node {
+        self.apply(Op::Implies, a, b)
+    }
+
+    /// Builds the diagram of an expression, adding its variables in the order they first
+    /// appear if they are new.
+    pub fn build(&mut self, e: &Expr) -> Node {
+        match e {
+            Expr::Const(m) => self.constant(*m),
+            Expr::Var(name) => self.var(name),
+            Expr::Not(a) => {
+                let a = self.build(a);
+                self.not(a)
+            }
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Xor(a, b) | Expr::Implies(a, b) => {
+                let (a, b) = (self.build(a), self.build(b));
+                let op = match e {
+                    Expr::And(..) => Op::And,
+                    Expr::Or(..) => Op::Or,
+                    Expr::Xor(..) => Op::Xor,
+                    _ => Op::Implies,
+                };
+                self.apply(op, a, b)
+            }
+        }
+    }
+
+    /// Evaluates `node` under an assignment. Only the variables it tests need to be bound.
+    pub fn eval<E: Env + ?Sized>(
+        &self,
+        node: Node,
+        env: &E,
+    ) -> Result<MsoTriState, UnboundVariable> {
+        let mut node = node;
+        loop {
+            match self.entries[node.0] {
+                Entry::Terminal(m) => return Ok(m),
+                Entry::Branch { level, children } => {
+                    let name = &self.vars[level];
+                    let m = env.get(name).ok_or_else(|| UnboundVariable(name.clone()))?;
+                    node = children[branch(m)];
+                }
+            }
+        }
+    }
+
+    /// Returns `node` with a variable fixed to a value.
+    pub fn restrict(&mut self, node: Node, name: &str, m: MsoTriState) -> Node {
+        match self.levels.get(name) {
+            Some(&level) => self.restrict_level(node, level, branch(m), &mut BTreeMap::new()),
+            None => node,
+        }
+    }
+
+    fn restrict_level(
+        &mut self,
+        node: Node,
+        level: usize,
+        index: usize,
+        cache: &mut BTreeMap<Node, Node>,
+    ) -> Node {
+        let (l, children) = match self.entries[node.0] {
+            Entry::Branch { level: l, children } if l <= level => (l, children),
+            _ => return node,
+        };
+        if l == level {
+            return children[index];
+        }
+        if let Some(&restricted) = cache.get(&node) {
+            return restricted;
+        }
+        let children = [
+            self.restrict_level(children[0], level, index, cache),
+            self.restrict_level(children[1], level, index, cache),
+            self.restrict_level(children[2], level, index, cache),
+        ];
+        let restricted = self.branch(l, children);
+        cache.insert(node, restricted);
+        restricted
+    }
+
+    /// Returns the `|` of `node` over every value of a variable: the best value it can take.
+    pub fn exists(&mut self, node: Node, name: &str) -> Node {
+        let [t, f, u] = self.restrictions(node, name);
+        let tf = self.or(t, f);
+        self.or(tf, u)
+    }
+
+    /// Returns the `&` of `node` over every value of a variable: the worst value it can take.
+    pub fn forall(&mut self, node: Node, name: &str) -> Node {
+        let [t, f, u] = self.restrictions(node, name);
+        let tf = self.and(t, f);
+        self.and(tf, u)
+    }
+
+    fn restrictions(&mut self, node: Node, name: &str) -> [Node; 3] {
+        [
+            self.restrict(node, name, msoTrue),
+            self.restrict(node, name, msoFalse),
+            self.restrict(node, name, msoTriStateMixed),
+        ]
+    }
+
+    /// Returns the number of assignments to all of the store's variables under which `node`
+    /// evaluates to `m`, or `None` if it doesn't fit in a `u128`.
+    ///
+    /// There are `3^n` assignments to `n` variables, so this can only overflow with more than
+    /// 80 variables.
+    pub fn count(&self, node: Node, m: MsoTriState) -> Option<u128> {
+        let n = self.vars.len();
+        let level = |node: Node| self.level(node).min(n);
+        // The count for `node` over the variables from its level on, scaled to those from `from`.
+        let scaled = |count: Option<u128>, node: Node, from: usize| match count {
+            Some(0) => Some(0),
+            count => 3u128
+                .checked_pow((level(node) - from) as u32)?
+                .checked_mul(count?),
+        };
+        let mut counts: BTreeMap<Node, Option<u128>> = BTreeMap::new();
+        for node in self.reachable(node).into_iter().rev() {
+            let count = match self.entries[node.0] {
+                Entry::Terminal(t) => Some((branch(t) == branch(m)) as u128),
+                Entry::Branch { level: l, children } => {
+                    children.iter().try_fold(0u128, |sum, child| {
+                        sum.checked_add(scaled(counts[child], *child, l + 1)?)
+                    })
+                }
+            };
+            counts.insert(node, count);
+        }
+        scaled(counts[&node], node, 0)
+    }
+
+    /// Returns the nodes reachable from `node`, each before its children.
+    fn reachable(&self, node: Node) -> Vec<Node> {
+        let mut order = Vec::new();
+        let mut seen = vec![false; self.entries.len()];
+        let mut stack = vec![node];
+        seen[node.0] = true;
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            if let Entry::Branch { children, .. } = self.entries[node.0] {
+                for child in &children {
+                    if !seen[child.0] {
+                        seen[child.0] = true;
+                        stack.push(*child);
+                    }
+                }
+            }
+        }
+        // Children always have a higher level, and terminals come last.
+        order.sort_by_key(|&node| (self.level(node), node));
+        order
+    }
+
+    /// Returns the number of nodes in the diagram of `node`, including terminals.
+    pub fn size(&self, node: Node) -> usize {
+        self.reachable(node).len()
+    }
+
+    /// Renders the diagram of `node` in the Graphviz DOT language.
+    ///
+    /// Edges for `msoTrue` are solid, for `msoFalse` dashed, and for `msoTriStateMixed` dotted.
+    pub fn to_dot(&self, node: Node) -> String {
+        const STYLES: [&str; 3] = ["dashed", "dotted", "solid"];
+        let mut out = String::from("digraph mdd {\n");
+        for node in self.reachable(node) {
+            // Writing to a String can't fail.
+            let _ = match self.entries[node.0] {
+                Entry::Terminal(m) => writeln!(out, "  n{} [label=\"{}\", shape=box];", node.0, m),
+                Entry::Branch { level, children } => {
+                    let _ = writeln!(out, "  n{} [label=\"{}\"];", node.0, self.vars[level]);
+                    children.iter().zip(&STYLES).try_for_each(|(child, style)| {
+                        writeln!(out, "  n{} -> n{} [style={}];", node.0, child.0, style)
+                    })
+                }
+            };
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{assignments, Rng};
+
+    fn store() -> Store {
+        let mut store = Store::new();
+        for name in &["a", "b", "c"] {
+            store.var(name);
+        }
+        store
+    }
+
+    #[test]
+    fn agrees_with_expr() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut store = store();
+        for _ in 0..300 {
+            let e = rng.expr(4);
+            let node = store.build(&e);
+            assert_eq!(node, store.build(&e.simplify()), "{}", e);
+            assert_eq!(node, store.build(&e.to_dnf().to_expr()), "{}", e);
+
+            let envs = assignments(&MsoTriState::CORE);
+            for env in &envs {
+                assert_eq!(store.eval(node, env), e.eval(env), "{}", e);
+            }
+            for &m in &MsoTriState::CORE {
+                let expected = envs.iter().filter(|env| e.eval(*env) == Ok(m)).count();
+                assert_eq!(store.count(node, m), Some(expected as u128), "{}", e);
+            }
+
+            for &x in &MsoTriState::CORE {
+                let restricted = store.restrict(node, "b", x);
+                for env in &envs {
+                    let mut fixed = env.clone();
+                    fixed.insert("b".into(), x);
+                    assert_eq!(store.eval(restricted, env), e.eval(&fixed));
+                }
+            }
+
+            let (exists, forall) = (store.exists(node, "a"), store.forall(node, "a"));
+            for env in &envs {
+                let values = MsoTriState::CORE.iter().map(|&x| {
+                    let mut fixed = env.clone();
+                    fixed.insert("a".into(), x);
+                    e.eval(&fixed).unwrap()
+                });
+                assert_eq!(
+                    store.eval(exists, env),
+                    Ok(values.clone().max_by_key(|m| rank(*m)).unwrap())
+                );
+                assert_eq!(
+                    store.eval(forall, env),
+                    Ok(values.min_by_key(|m| rank(*m)).unwrap())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reduced() {
+        let mut store = store();
+        let a = store.var("a");
+        assert_eq!(store.size(a), 4);
+        let contradiction = store.build(&"a & !a".parse().unwrap());
+        assert_ne!(contradiction, store.constant(msoFalse));
+        let never_true = store.build(&"(a & !a) & msoTrue".parse().unwrap());
+        assert_eq!(never_true, contradiction);
+        let constant = store.build(&"a & !a & msoFalse".parse().unwrap());
+        assert_eq!(store.as_constant(constant), Some(msoFalse));
+        assert_eq!(store.restrict(a, "z", msoTrue), a);
+        assert_eq!(store.count(store.constant(msoTrue), msoTrue), Some(27));
+    }
+
+    #[test]
+    fn count_many_variables() {
+        let mut store = Store::new();
+        let mut all = store.constant(msoTrue);
+        for i in 0..100 {
+            let x = store.var(&alloc::format!("x{}", i));
+            all = store.and(all, x);
+        }
+        // Exactly one assignment makes the conjunction true, and 2^100 make it not false.
+        assert_eq!(store.count(all, msoTrue), Some(1));
+        assert_eq!(store.count(store.constant(msoFalse), msoTrue), Some(0));
+        assert_eq!(store.count(store.constant(msoTrue), msoTrue), None);
+        assert_eq!(store.count(all, msoFalse), None);
+        let not_false = store.count(all, msoTriStateMixed).map(|c| c + 1);
+        assert_eq!(not_false, Some(1 << 100));
+    }
+
+    #[test]
+    fn eval_unbound() {
+        let mut store = store();
+        let node = store.build(&"a & c".parse().unwrap());
+        let env = |name: &str| if name == "a" { Some(msoFalse) } else { None };
+        assert_eq!(store.eval(node, &env), Ok(msoFalse));
+        let env = |name: &str| if name == "a" { Some(msoTrue) } else { None };
+        assert_eq!(store.eval(node, &env), Err(UnboundVariable("c".into())));
+    }
+
+    #[test]
+    fn dot() {
+        let mut store = Store::new();
+        let node = store.build(&"a & b".parse().unwrap());
+        assert_eq!(
+            store.to_dot(node),
+            "digraph mdd {
+  n6 [label=\"a\"];
+  n6 -> n0 [style=dashed];
+  n6 -> n5 [style=dotted];
+  n6 -> n4 [style=solid];
+  n4 [label=\"b\"];
+  n4 -> n0 [style=dashed];
+  n4 -> n1 [style=dotted];
+  n4 -> n2 [style=solid];
+  n5 [label=\"b\"];
+  n5 -> n0 [style=dashed];
+  n5 -> n1 [style=dotted];
+  n5 -> n1 [style=solid];
+  n0 [label=\"msoFalse\", shape=box];
+  n1 [label=\"msoTriStateMixed\", shape=box];
+  n2 [label=\"msoTrue\", shape=box];
+}
+"
+        );
+    }
+}
@@ -0,0 +1,529 @@
+//! An in-memory stand-in for the PowerPoint object model, for testing automation code.
+//!
+//! Tri-state properties accept and report values as described by the `caps` module:
+//!
+//! | Property                             | Writes                   | Reads                   |
+//! |--------------------------------------|--------------------------|-------------------------|
+//! | `Shape.Visible`                      | `msoTrue`, `msoFalse`    | `msoTrue`, `msoFalse`   |
+//! | `Shape.LockAspectRatio`              | `msoTrue`, `msoFalse`    | `msoTrue`, `msoFalse`   |
+//! | `Shape.HasTextFrame`                 | read-only                | `msoTrue`, `msoFalse`   |
+//! | `ShapeRange.Visible`                 | `msoTrue`, `msoFalse`    | also `msoTriStateMixed` |
+//! | `ShapeRange.LockAspectRatio`         | `msoTrue`, `msoFalse`    | also `msoTriStateMixed` |
+//! | `Font.Bold`, `.Italic`, `.Underline` | also `msoTriStateToggle` | also `msoTriStateMixed` |
+//!
+//! Writing any other value fails with `OfficeError::ValueOutOfRange`, as Office raises a
+//! run-time error. Collections are indexed from 1, as in Office.
+//!
+//! ```
+//! use mso_tri_state::office::Presentation;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let mut presentation = Presentation::new();
+//! let slide = presentation.slides_add(1).unwrap();
+//! slide.add_textbox("Title", "Hello, world");
+//!
+//! let shape = presentation.slide(1).unwrap().shape(1).unwrap();
+//! assert_eq!(shape.has_text_frame(), msoTrue);
+//! let mut range = shape.text_range().unwrap().characters(1, 5);
+//! range.font().set_bold(msoTrue).unwrap();
+//!
+//! let mut all = shape.text_range().unwrap();
+//! assert_eq!(all.font().bold(), msoTriStateMixed);
+//! assert!(all.font().set_bold(msoTriStateMixed).is_err());
+//! ```
+
+use crate::aggregate::Aggregate;
+use crate::caps::{self, CapsError, PropertyCaps};
+use crate::text::{Format, FormattedText, Property};
+use crate::MsoTriState;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+/// The error returned where Office would raise a run-time error.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OfficeError {
+    /// The value isn't supported by the property.
+    ValueOutOfRange {
+        /// The property, such as `Shape.Visible`.
+        property: &'static str,
+        /// The rejected value.
+        value: MsoTriState,
+    },
+    /// The property is read-only.
+    ReadOnly(&'static str),
+    /// No item of a collection has this index.
+    IndexOutOfRange(usize),
+    /// The shape has no text frame.
+    NoTextFrame(String),
+}
+
+impl fmt::Display for OfficeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OfficeError::ValueOutOfRange { property, value } => {
+                write!(f, "{}: the value {} is out of range", property, value)
+            }
+            OfficeError::ReadOnly(property) => write!(f, "{} is read-only", property),
+            OfficeError::IndexOutOfRange(i) => write!(f, "index {} is out of range", i),
+            OfficeError::NoTextFrame(name) => write!(f, "shape {} has no text frame", name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OfficeError {}
+
+impl From<CapsError> for OfficeError {
+    fn from(e: CapsError) -> OfficeError {
+        match e {
+            CapsError::ReadOnly(property) => OfficeError::ReadOnly(property),
+            CapsError::NotAccepted { property, value } => {
+                OfficeError::ValueOutOfRange { property, value }
+            }
+        }
+    }
+}
+
+/// Looks up a 1-based index.
+fn item<T>(items: &[T], index: usize) -> Result<&T, OfficeError> {
+    index
+        .checked_sub(1)
+        .and_then(|i| items.get(i))
+        .ok_or(OfficeError::IndexOutOfRange(index))
+}
+
+fn item_mut<T>(items: &mut [T], index: usize) -> Result<&mut T, OfficeError> {
+    index
+        .checked_sub(1)
+        .and_then(move |i| items.get_mut(i))
+        .ok_or(OfficeError::IndexOutOfRange(index))
+}
+
+/// A presentation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Presentation {
+    slides: Vec<Slide>,
+}
+
+impl Presentation {
+    /// Creates a presentation with no slides.
+    pub fn new() -> Presentation {
+        Presentation::default()
+    }
+
+    /// `Slides.Count`.
+    pub fn slides_count(&self) -> usize {
+        self.slides.len()
+    }
+
+    /// `Slides.Add`: inserts an empty slide at a 1-based index and returns it.
+    pub fn slides_add(&mut self, index: usize) -> Result<&mut Slide, OfficeError> {
+        if index == 0 || index > self.slides.len() + 1 {
+            return Err(OfficeError::IndexOutOfRange(index));
+        }
+        self.slides.insert(index - 1, Slide::default());
+        Ok(&mut self.slides[index - 1])
+    }
+
+    /// `Slides(index)`.
+    pub fn slide(&mut self, index: usize) -> Result<&mut Slide, OfficeError> {
+        item_mut(&mut self.slides, index)
+    }
+}
+
+/// A slide.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Slide {
+    shapes: Vec<Shape>,
+}
+
+impl Slide {
+    fn add(&mut self, name: &str, text_frame: Option<FormattedText>) -> &mut Shape {
+        self.shapes.push(Shape {
+            name: name.to_string(),
+            visible: true,
+            lock_aspect_ratio: false,
+            text_frame,
+        });
+        self.shapes.last_mut().unwrap()
+    }
+
+    /// `Shapes.AddTextbox`: adds a shape with a text frame holding `text`.
+    pub fn add_textbox(&mut self, name: &str, text: &str) -> &mut Shape {
+        let mut frame = FormattedText::new();
+        frame.push(text, Format::default());
+        self.add(name, Some(frame))
+    }
+
+    /// `Shapes.AddPicture`: adds a shape without a text frame. Pictures lock their aspect ratio.
+    pub fn add_picture(&mut self, name: &str) -> &mut Shape {
+        let shape = self.add(name, None);
+        shape.lock_aspect_ratio = true;
+        shape
+    }
+
+    /// `Shapes.Count`.
+    pub fn shapes_count(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// `Shapes(index)`.
+    pub fn shape(&mut self, index: usize) -> Result<&mut Shape, OfficeError> {
+        item_mut(&mut self.shapes, index)
+    }
+
+    /// `Shapes.Range(indices)`.
+    pub fn shape_range(&mut self, indices: &[usize]) -> Result<ShapeRange<'_>, OfficeError> {
+        for &i in indices {
+            item(&self.shapes, i)?;
+        }
+        Ok(ShapeRange {
+            slide: self,
+            indices: indices.iter().map(|i| i - 1).collect(),
+        })
+    }
+}
+
+/// A shape.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    name: String,
+    visible: bool,
+    lock_aspect_ratio: bool,
+    text_frame: Option<FormattedText>,
+}
+
+impl Shape {
+    /// `Shape.Name`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `Shape.Visible`.
+    pub fn visible(&self) -> MsoTriState {
+        self.visible.into()
+    }
+
+    /// Sets `Shape.Visible`.
+    pub fn set_visible(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        caps::SHAPE_VISIBLE.check(value)?;
+        self.visible = value == MsoTriState::msoTrue;
+        Ok(())
+    }
+
+    /// `Shape.LockAspectRatio`.
+    pub fn lock_aspect_ratio(&self) -> MsoTriState {
+        self.lock_aspect_ratio.into()
+    }
+
+    /// Sets `Shape.LockAspectRatio`.
+    pub fn set_lock_aspect_ratio(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        caps::SHAPE_LOCK_ASPECT_RATIO.check(value)?;
+        self.lock_aspect_ratio = value == MsoTriState::msoTrue;
+        Ok(())
+    }
+
+    /// `Shape.HasTextFrame`.
+    pub fn has_text_frame(&self) -> MsoTriState {
+        self.text_frame.is_some().into()
+    }
+
+    /// Sets `Shape.HasTextFrame`, which always fails because the property is read-only.
+    pub fn set_has_text_frame(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        caps::SHAPE_HAS_TEXT_FRAME.check(value)?;
+        Ok(())
+    }
+
+    /// `Shape.TextFrame.TextRange`.
+    pub fn text_range(&mut self) -> Result<TextRange<'_>, OfficeError> {
+        match &mut self.text_frame {
+            Some(text) => {
+                let range = 0..text.len();
+                Ok(TextRange { text, range })
+            }
+            None => Err(OfficeError::NoTextFrame(self.name.clone())),
+        }
+    }
+}
+
+/// A range of shapes on one slide.
+#[derive(Debug)]
+pub struct ShapeRange<'a> {
+    slide: &'a mut Slide,
+    indices: Vec<usize>,
+}
+
+impl ShapeRange<'_> {
+    fn get(&self, f: fn(&Shape) -> bool) -> MsoTriState {
+        self.indices
+            .iter()
+            .map(|&i| f(&self.slide.shapes[i]))
+            .aggregate()
+    }
+
+    /// `ShapeRange.Visible`.
+    pub fn visible(&self) -> MsoTriState {
+        self.get(|s| s.visible)
+    }
+
+    /// Sets `ShapeRange.Visible`.
+    pub fn set_visible(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        caps::SHAPE_RANGE_VISIBLE.check(value)?;
+        for &i in &self.indices {
+            self.slide.shapes[i].visible = value == MsoTriState::msoTrue;
+        }
+        Ok(())
+    }
+
+    /// `ShapeRange.LockAspectRatio`.
+    pub fn lock_aspect_ratio(&self) -> MsoTriState {
+        self.get(|s| s.lock_aspect_ratio)
+    }
+
+    /// Sets `ShapeRange.LockAspectRatio`.
+    pub fn set_lock_aspect_ratio(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        caps::SHAPE_RANGE_LOCK_ASPECT_RATIO.check(value)?;
+        for &i in &self.indices {
+            self.slide.shapes[i].lock_aspect_ratio = value == MsoTriState::msoTrue;
+        }
+        Ok(())
+    }
+}
+
+/// A range of text in a text frame.
+#[derive(Debug)]
+pub struct TextRange<'a> {
+    text: &'a mut FormattedText,
+    range: Range<usize>,
+}
+
+impl<'a> TextRange<'a> {
+    /// `TextRange.Text`.
+    pub fn text(&self) -> String {
+        self.text
+            .text()
+            .chars()
+            .skip(self.range.start)
+            .take(self.range.len())
+            .collect()
+    }
+
+    /// `TextRange.Characters(start, length)`: the subrange starting at the 1-based character
+    /// `start`, clamped to this range as Office does.
+    pub fn characters(self, start: usize, length: usize) -> TextRange<'a> {
+        let start = (self.range.start + start.saturating_sub(1)).min(self.range.end);
+        let end = start.saturating_add(length).min(self.range.end);
+        TextRange {
+            text: self.text,
+            range: start..end,
+        }
+    }
+
+    /// `TextRange.Font`.
+    pub fn font(&mut self) -> Font<'_> {
+        Font {
+            text: self.text,
+            range: self.range.clone(),
+        }
+    }
+}
+
+/// The character formatting of a `TextRange`.
+#[derive(Debug)]
+pub struct Font<'a> {
+    text: &'a mut FormattedText,
+    range: Range<usize>,
+}
+
+impl Font<'_> {
+    fn set(
+        &mut self,
+        caps: &PropertyCaps,
+        property: Property,
+        value: MsoTriState,
+    ) -> Result<(), OfficeError> {
+        caps.check(value)?;
+        self.text
+            .set(self.range.clone(), property, value)
+            .map_err(|_| OfficeError::ValueOutOfRange {
+                property: caps.name,
+                value,
+            })
+    }
+
+    /// `Font.Bold`.
+    pub fn bold(&self) -> MsoTriState {
+        self.text.get(self.range.clone(), Property::Bold)
+    }
+
+    /// Sets `Font.Bold`.
+    pub fn set_bold(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        self.set(&caps::FONT_BOLD, Property::Bold, value)
+    }
+
+    /// `Font.Italic`.
+    pub fn italic(&self) -> MsoTriState {
+        self.text.get(self.range.clone(), Property::Italic)
+    }
+
+    /// Sets `Font.Italic`.
+    pub fn set_italic(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        self.set(&caps::FONT_ITALIC, Property::Italic, value)
+    }
+
+    /// `Font.Underline`.
+    pub fn underline(&self) -> MsoTriState {
+        self.text.get(self.range.clone(), Property::Underline)
+    }
+
+    /// Sets `Font.Underline`.
+    pub fn set_underline(&mut self, value: MsoTriState) -> Result<(), OfficeError> {
+        self.set(&caps::FONT_UNDERLINE, Property::Underline, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    fn presentation() -> Presentation {
+        let mut presentation = Presentation::new();
+        let slide = presentation.slides_add(1).unwrap();
+        slide.add_textbox("Title", "Hello, world");
+        slide.add_picture("Logo");
+        presentation
+    }
+
+    #[test]
+    fn collections() {
+        let mut presentation = presentation();
+        assert_eq!(presentation.slides_count(), 1);
+        assert_eq!(
+            presentation.slides_add(3).unwrap_err(),
+            OfficeError::IndexOutOfRange(3)
+        );
+        presentation.slides_add(1).unwrap();
+        assert_eq!(presentation.slide(1).unwrap().shapes_count(), 0);
+
+        let slide = presentation.slide(2).unwrap();
+        assert_eq!(slide.shapes_count(), 2);
+        assert_eq!(slide.shape(2).unwrap().name(), "Logo");
+        assert_eq!(slide.shape(0).unwrap_err(), OfficeError::IndexOutOfRange(0));
+        assert_eq!(slide.shape(3).unwrap_err(), OfficeError::IndexOutOfRange(3));
+        assert_eq!(
+            slide.shape_range(&[1, 4]).unwrap_err(),
+            OfficeError::IndexOutOfRange(4)
+        );
+    }
+
+    #[test]
+    fn shape_properties() {
+        let mut presentation = presentation();
+        let shape = presentation.slide(1).unwrap().shape(2).unwrap();
+        assert_eq!(shape.visible(), msoTrue);
+        assert_eq!(shape.lock_aspect_ratio(), msoTrue);
+        assert_eq!(shape.has_text_frame(), msoFalse);
+
+        shape.set_visible(msoFalse).unwrap();
+        assert_eq!(shape.visible(), msoFalse);
+        shape.set_lock_aspect_ratio(msoFalse).unwrap();
+        assert_eq!(shape.lock_aspect_ratio(), msoFalse);
+
+        for &value in &[msoCTrue, msoTriStateMixed, msoTriStateToggle] {
+            assert_eq!(
+                shape.set_visible(value),
+                Err(OfficeError::ValueOutOfRange {
+                    property: "Shape.Visible",
+                    value,
+                })
+            );
+            assert!(shape.set_lock_aspect_ratio(value).is_err());
+        }
+        assert_eq!(
+            shape.set_has_text_frame(msoTrue),
+            Err(OfficeError::ReadOnly("Shape.HasTextFrame"))
+        );
+        assert_eq!(
+            shape.text_range().unwrap_err(),
+            OfficeError::NoTextFrame("Logo".to_string())
+        );
+    }
+
+    #[test]
+    fn shape_range() {
+        let mut presentation = presentation();
+        let slide = presentation.slide(1).unwrap();
+        let mut range = slide.shape_range(&[1, 2]).unwrap();
+        assert_eq!(range.visible(), msoTrue);
+        assert_eq!(range.lock_aspect_ratio(), msoTriStateMixed);
+
+        range.set_lock_aspect_ratio(msoTrue).unwrap();
+        assert_eq!(range.lock_aspect_ratio(), msoTrue);
+        assert!(range.set_visible(msoTriStateMixed).is_err());
+        range.set_visible(msoFalse).unwrap();
+        assert_eq!(slide.shape(1).unwrap().visible(), msoFalse);
+    }
+
+    #[test]
+    fn font() {
+        let mut presentation = presentation();
+        let shape = presentation.slide(1).unwrap().shape(1).unwrap();
+        let mut world = shape.text_range().unwrap().characters(8, 5);
+        assert_eq!(world.text(), "world");
+        world.font().set_italic(msoTrue).unwrap();
+
+        let mut all = shape.text_range().unwrap();
+        assert_eq!(all.font().italic(), msoTriStateMixed);
+        all.font().set_italic(msoTriStateToggle).unwrap();
+        assert_eq!(all.font().italic(), msoTrue);
+        all.font().set_underline(msoTrue).unwrap();
+        assert_eq!(all.font().underline(), msoTrue);
+        assert_eq!(all.font().bold(), msoFalse);
+
+        assert_eq!(
+            all.font().set_bold(msoTriStateMixed),
+            Err(OfficeError::ValueOutOfRange {
+                property: "Font.Bold",
+                value: msoTriStateMixed,
+            })
+        );
+        assert!(all.font().set_bold(msoCTrue).is_err());
+
+        let clamped = shape.text_range().unwrap().characters(10, 100);
+        assert_eq!(clamped.text(), "rld");
+        let to_end = shape.text_range().unwrap().characters(2, usize::MAX);
+        assert_eq!(to_end.text(), "ello, world");
+    }
+
+    #[test]
+    fn reads_match_caps() {
+        let mut presentation = presentation();
+        let slide = presentation.slide(1).unwrap();
+        for i in 1..=2 {
+            let shape = slide.shape(i).unwrap();
+            assert!(caps::SHAPE_VISIBLE.can_return(shape.visible()));
+            assert!(caps::SHAPE_LOCK_ASPECT_RATIO.can_return(shape.lock_aspect_ratio()));
+            assert!(caps::SHAPE_HAS_TEXT_FRAME.can_return(shape.has_text_frame()));
+        }
+        let range = slide.shape_range(&[1, 2]).unwrap();
+        assert!(caps::SHAPE_RANGE_VISIBLE.can_return(range.visible()));
+        assert!(caps::SHAPE_RANGE_LOCK_ASPECT_RATIO.can_return(range.lock_aspect_ratio()));
+
+        let shape = slide.shape(1).unwrap();
+        shape
+            .text_range()
+            .unwrap()
+            .characters(1, 1)
+            .font()
+            .set_bold(msoTrue)
+            .unwrap();
+        let mut all = shape.text_range().unwrap();
+        let font = all.font();
+        assert!(caps::FONT_BOLD.can_return(font.bold()));
+        assert!(caps::FONT_ITALIC.can_return(font.italic()));
+        assert!(caps::FONT_UNDERLINE.can_return(font.underline()));
+    }
+}
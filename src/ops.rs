@@ -0,0 +1,136 @@
+//! Strong Kleene logic operators.
+//!
+//! `msoTriStateMixed` is the unknown value. Operands are normalized first (see
+//! `MsoTriState::normalize`), so `msoCTrue` behaves as `msoTrue` and `msoTriStateToggle` as
+//! `msoTriStateMixed`, and results are always `msoTrue`, `msoFalse` or `msoTriStateMixed`.
+//!
+//! Other logic systems are available in the `logic` module.
+
+use crate::MsoTriState;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+/// Ranks a value from false (0) through unknown (1) to true (2).
+pub(crate) const fn rank(m: MsoTriState) -> u8 {
+    match m.normalize() {
+        MsoTriState::msoFalse => 0,
+        MsoTriState::msoTrue => 2,
+        _ => 1,
+    }
+}
+
+const fn unrank(r: u8) -> MsoTriState {
+    MsoTriState::CORE[r as usize]
+}
+
+impl Not for MsoTriState {
+    type Output = MsoTriState;
+
+    fn not(self) -> MsoTriState {
+        unrank(2 - rank(self))
+    }
+}
+
+impl BitAnd for MsoTriState {
+    type Output = MsoTriState;
+
+    fn bitand(self, rhs: MsoTriState) -> MsoTriState {
+        unrank(rank(self).min(rank(rhs)))
+    }
+}
+
+impl BitOr for MsoTriState {
+    type Output = MsoTriState;
+
+    fn bitor(self, rhs: MsoTriState) -> MsoTriState {
+        unrank(rank(self).max(rank(rhs)))
+    }
+}
+
+impl BitXor for MsoTriState {
+    type Output = MsoTriState;
+
+    fn bitxor(self, rhs: MsoTriState) -> MsoTriState {
+        (self & !rhs) | (!self & rhs)
+    }
+}
+
+impl BitAndAssign for MsoTriState {
+    fn bitand_assign(&mut self, rhs: MsoTriState) {
+        *self = *self & rhs;
+    }
+}
+
+impl BitOrAssign for MsoTriState {
+    fn bitor_assign(&mut self, rhs: MsoTriState) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitXorAssign for MsoTriState {
+    fn bitxor_assign(&mut self, rhs: MsoTriState) {
+        *self = *self ^ rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MsoTriState::{self, *};
+
+    #[test]
+    fn not() {
+        assert_eq!(!msoFalse, msoTrue);
+        assert_eq!(!msoTriStateMixed, msoTriStateMixed);
+        assert_eq!(!msoTrue, msoFalse);
+        assert_eq!(!msoCTrue, msoFalse);
+        assert_eq!(!msoTriStateToggle, msoTriStateMixed);
+    }
+
+    #[test]
+    fn truth_tables() {
+        // Rows and columns in the order of `MsoTriState::CORE`: F, U, T.
+        let and = [
+            [msoFalse, msoFalse, msoFalse],
+            [msoFalse, msoTriStateMixed, msoTriStateMixed],
+            [msoFalse, msoTriStateMixed, msoTrue],
+        ];
+        let or = [
+            [msoFalse, msoTriStateMixed, msoTrue],
+            [msoTriStateMixed, msoTriStateMixed, msoTrue],
+            [msoTrue, msoTrue, msoTrue],
+        ];
+        let xor = [
+            [msoFalse, msoTriStateMixed, msoTrue],
+            [msoTriStateMixed, msoTriStateMixed, msoTriStateMixed],
+            [msoTrue, msoTriStateMixed, msoFalse],
+        ];
+
+        for (i, &a) in MsoTriState::CORE.iter().enumerate() {
+            for (j, &b) in MsoTriState::CORE.iter().enumerate() {
+                assert_eq!(a & b, and[i][j], "{} & {}", a, b);
+                assert_eq!(a | b, or[i][j], "{} | {}", a, b);
+                assert_eq!(a ^ b, xor[i][j], "{} ^ {}", a, b);
+
+                let mut c = a;
+                c &= b;
+                assert_eq!(c, and[i][j]);
+                let mut c = a;
+                c |= b;
+                assert_eq!(c, or[i][j]);
+                let mut c = a;
+                c ^= b;
+                assert_eq!(c, xor[i][j]);
+            }
+        }
+    }
+
+    #[test]
+    fn normalized_operands() {
+        for &a in &MsoTriState::ALL {
+            for &b in &MsoTriState::ALL {
+                assert_eq!(a & b, a.normalize() & b.normalize());
+                assert_eq!(a | b, a.normalize() | b.normalize());
+                assert_eq!(a ^ b, a.normalize() ^ b.normalize());
+            }
+        }
+    }
+}
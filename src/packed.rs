@@ -0,0 +1,294 @@
+//! Dense packing of five `MsoTriState` values per byte.
+//!
+//! Three of the five values are chosen by an `Alphabet` and stored as trits, five to a byte
+//! (3<sup>5</sup> = 243), least significant trit first. Byte values 243 to 255 are never used.
+//!
+//! The other two values are escaped: each one is recorded out of line as an `(index, value)`
+//! pair, sorted by index, and its trit slot holds zero. Escapes cost a lookup in random access,
+//! so they should be rare.
+//!
+//! ```
+//! use mso_tri_state::packed::{Alphabet, PackedTrits};
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let values = [msoTrue, msoFalse, msoTriStateMixed, msoTrue, msoTrue, msoFalse];
+//! let packed = PackedTrits::encode(&values, Alphabet::Kleene);
+//! assert_eq!(packed.as_bytes().len(), 2);
+//! assert_eq!(packed.get(2), Some(msoTriStateMixed));
+//! assert_eq!(packed.decode(), values);
+//! ```
+
+use crate::MsoTriState;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Which three values are stored as trits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    /// `msoFalse`, `msoTriStateMixed` and `msoTrue` as 0, 1 and 2. `msoCTrue` and
+    /// `msoTriStateToggle` are escaped.
+    Kleene,
+    /// The balanced-ternary trits `msoTrue`, `msoFalse` and `msoCTrue` as 0, 1 and 2.
+    /// `msoTriStateMixed` and `msoTriStateToggle` are escaped.
+    BalancedTernary,
+}
+
+impl Alphabet {
+    fn values(self) -> [MsoTriState; 3] {
+        match self {
+            Alphabet::Kleene => [
+                MsoTriState::msoFalse,
+                MsoTriState::msoTriStateMixed,
+                MsoTriState::msoTrue,
+            ],
+            Alphabet::BalancedTernary => [
+                MsoTriState::msoTrue,
+                MsoTriState::msoFalse,
+                MsoTriState::msoCTrue,
+            ],
+        }
+    }
+
+    fn trit(self, m: MsoTriState) -> Option<u8> {
+        self.values().iter().position(|&v| v == m).map(|t| t as u8)
+    }
+}
+
+const POWERS: [u8; 5] = [1, 3, 9, 27, 81];
+
+/// `MsoTriState` values packed five to a byte.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PackedTrits {
+    alphabet: Alphabet,
+    len: usize,
+    bytes: Vec<u8>,
+    escapes: Vec<(usize, MsoTriState)>,
+}
+
+impl PackedTrits {
+    /// Packs a slice of values.
+    pub fn encode(values: &[MsoTriState], alphabet: Alphabet) -> PackedTrits {
+        let mut bytes = vec![0; values.len().div_ceil(5)];
+        let mut escapes = Vec::new();
+        for (i, &m) in values.iter().enumerate() {
+            match alphabet.trit(m) {
+                Some(t) => bytes[i / 5] += t * POWERS[i % 5],
+                None => escapes.push((i, m)),
+            }
+        }
+        PackedTrits {
+            alphabet,
+            len: values.len(),
+            bytes,
+            escapes,
+        }
+    }
+
+    /// Reassembles packed values from their parts, as returned by `as_bytes` and `escapes`.
+    pub fn from_parts(
+        alphabet: Alphabet,
+        len: usize,
+        bytes: Vec<u8>,
+        escapes: Vec<(usize, MsoTriState)>,
+    ) -> Result<PackedTrits, PackedError> {
+        if bytes.len() != len.div_ceil(5) {
+            return Err(PackedError::Length);
+        }
+        if let Some(&b) = bytes.iter().find(|&&b| b >= 243) {
+            return Err(PackedError::Byte(b));
+        }
+        // Trits past the end must be zero, so that equal values have equal bytes.
+        if let Some(&b) = bytes
+            .last()
+            .filter(|&&b| len % 5 != 0 && b >= POWERS[len % 5])
+        {
+            return Err(PackedError::Byte(b));
+        }
+        let mut last = None;
+        for &(i, m) in &escapes {
+            if i >= len
+                || last.is_some_and(|l| l >= i)
+                || alphabet.trit(m).is_some()
+                || bytes[i / 5] / POWERS[i % 5] % 3 != 0
+            {
+                return Err(PackedError::Escape(i));
+            }
+            last = Some(i);
+        }
+        Ok(PackedTrits {
+            alphabet,
+            len,
+            bytes,
+            escapes,
+        })
+    }
+
+    /// Returns the alphabet.
+    pub fn alphabet(&self) -> Alphabet {
+        self.alphabet
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the packed trits.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the escaped values, sorted by index.
+    pub fn escapes(&self) -> &[(usize, MsoTriState)] {
+        &self.escapes
+    }
+
+    /// Returns the value at an index.
+    pub fn get(&self, index: usize) -> Option<MsoTriState> {
+        if index >= self.len {
+            return None;
+        }
+        if !self.escapes.is_empty() {
+            if let Ok(e) = self.escapes.binary_search_by_key(&index, |&(i, _)| i) {
+                return Some(self.escapes[e].1);
+            }
+        }
+        let t = self.bytes[index / 5] / POWERS[index % 5] % 3;
+        Some(self.alphabet.values()[t as usize])
+    }
+
+    /// Unpacks every value.
+    pub fn decode(&self) -> Vec<MsoTriState> {
+        let values = self.alphabet.values();
+        let mut out: Vec<MsoTriState> = self
+            .bytes
+            .iter()
+            .flat_map(|&b| POWERS.iter().map(move |&p| values[(b / p % 3) as usize]))
+            .take(self.len)
+            .collect();
+        for &(i, m) in &self.escapes {
+            out[i] = m;
+        }
+        out
+    }
+}
+
+/// The error returned when reassembling `PackedTrits` from invalid parts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PackedError {
+    /// The number of bytes doesn't match the number of values.
+    Length,
+    /// A byte is greater than 242, or the last byte has nonzero trits past the end.
+    Byte(u8),
+    /// An escape at this index is out of range, out of order, a value in the alphabet, or over
+    /// a nonzero trit.
+    Escape(usize),
+}
+
+impl fmt::Display for PackedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedError::Length => write!(f, "wrong number of bytes"),
+            PackedError::Byte(b) => write!(f, "invalid packed byte {}", b),
+            PackedError::Escape(i) => write!(f, "invalid escape at index {}", i),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PackedError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    #[test]
+    fn every_byte() {
+        let values = [msoFalse, msoTriStateMixed, msoTrue];
+        for b in 0..243u8 {
+            let group: Vec<_> = (0..5)
+                .map(|i| values[(b / POWERS[i] % 3) as usize])
+                .collect();
+            let packed = PackedTrits::encode(&group, Alphabet::Kleene);
+            assert_eq!(packed.as_bytes(), &[b]);
+            assert_eq!(packed.decode(), group);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for &alphabet in &[Alphabet::Kleene, Alphabet::BalancedTernary] {
+            for len in 0..23 {
+                let values: Vec<_> = (0..len)
+                    .map(|i| MsoTriState::ALL[(i * 7 + len) % 5])
+                    .collect();
+                let packed = PackedTrits::encode(&values, alphabet);
+                assert_eq!(packed.len(), len);
+                assert_eq!(packed.as_bytes().len(), len.div_ceil(5));
+                assert_eq!(packed.decode(), values);
+                for (i, &m) in values.iter().enumerate() {
+                    assert_eq!(packed.get(i), Some(m));
+                }
+                assert_eq!(packed.get(len), None);
+
+                let parts = PackedTrits::from_parts(
+                    alphabet,
+                    len,
+                    packed.as_bytes().to_vec(),
+                    packed.escapes().to_vec(),
+                );
+                assert_eq!(parts, Ok(packed));
+            }
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        let values = [msoCTrue, msoTrue, msoTriStateToggle];
+        let packed = PackedTrits::encode(&values, Alphabet::Kleene);
+        assert_eq!(packed.escapes(), &[(0, msoCTrue), (2, msoTriStateToggle)]);
+
+        let packed = PackedTrits::encode(&values, Alphabet::BalancedTernary);
+        assert_eq!(packed.escapes(), &[(2, msoTriStateToggle)]);
+        assert_eq!(packed.as_bytes(), &[2]);
+    }
+
+    #[test]
+    fn invalid_parts() {
+        let from_parts =
+            |len, bytes, escapes| PackedTrits::from_parts(Alphabet::Kleene, len, bytes, escapes);
+        assert_eq!(from_parts(6, vec![0], vec![]), Err(PackedError::Length));
+        assert_eq!(
+            from_parts(5, vec![243], vec![]),
+            Err(PackedError::Byte(243))
+        );
+        assert_eq!(
+            from_parts(5, vec![0], vec![(5, msoCTrue)]),
+            Err(PackedError::Escape(5))
+        );
+        assert_eq!(
+            from_parts(5, vec![0], vec![(3, msoCTrue), (1, msoCTrue)]),
+            Err(PackedError::Escape(1))
+        );
+        assert_eq!(
+            from_parts(5, vec![0], vec![(1, msoTrue)]),
+            Err(PackedError::Escape(1))
+        );
+
+        // Non-canonical parts would decode equal but compare unequal.
+        assert_eq!(from_parts(2, vec![9], vec![]), Err(PackedError::Byte(9)));
+        assert!(from_parts(2, vec![8], vec![]).is_ok());
+        assert_eq!(
+            from_parts(2, vec![3], vec![(1, msoCTrue)]),
+            Err(PackedError::Escape(1))
+        );
+        assert!(from_parts(2, vec![2], vec![(1, msoCTrue)]).is_ok());
+    }
+}
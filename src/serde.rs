@@ -0,0 +1,181 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! `MsoTriState` is serialized as its Office name, as printed by `Display`. The modules here can
+//! be used with `#[serde(with = "...")]` for other representations:
+//!
+//! ```
+//! use mso_tri_state::MsoTriState;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Settings {
+//!     bold: MsoTriState,
+//!     #[serde(with = "mso_tri_state::serde::int")]
+//!     italic: MsoTriState,
+//!     #[serde(with = "mso_tri_state::serde::nullable_bool")]
+//!     underline: MsoTriState,
+//! }
+//!
+//! let settings = Settings {
+//!     bold: MsoTriState::msoTrue,
+//!     italic: MsoTriState::msoTrue,
+//!     underline: MsoTriState::msoTriStateMixed,
+//! };
+//! assert_eq!(
+//!     serde_json::to_string(&settings).unwrap(),
+//!     r#"{"bold":"msoTrue","italic":-1,"underline":null}"#
+//! );
+//! ```
+
+use crate::MsoTriState;
+use ::serde::de::{self, Deserialize, Deserializer, Visitor};
+use ::serde::ser::{Serialize, Serializer};
+use core::fmt;
+
+impl Serialize for MsoTriState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+struct NameVisitor;
+
+impl Visitor<'_> for NameVisitor {
+    type Value = MsoTriState;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the name of an MsoTriState")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<MsoTriState, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for MsoTriState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MsoTriState, D::Error> {
+        deserializer.deserialize_str(NameVisitor)
+    }
+}
+
+/// Serializes `MsoTriState` as its Office integer value.
+pub mod int {
+    use crate::MsoTriState;
+    use ::serde::de::{self, Deserializer, Visitor};
+    use ::serde::ser::Serializer;
+    use core::convert::TryFrom;
+    use core::fmt;
+
+    /// Serializes the Office integer value.
+    pub fn serialize<S: Serializer>(m: &MsoTriState, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(m.to_i32())
+    }
+
+    struct IntVisitor;
+
+    impl Visitor<'_> for IntVisitor {
+        type Value = MsoTriState;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "one of 1, 0, -2, -3, -1")
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<MsoTriState, E> {
+            MsoTriState::try_from(v).map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<MsoTriState, E> {
+            let v = i64::try_from(v).map_err(|_| E::custom("value out of range"))?;
+            self.visit_i64(v)
+        }
+    }
+
+    /// Deserializes an Office integer value.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MsoTriState, D::Error> {
+        deserializer.deserialize_i32(IntVisitor)
+    }
+}
+
+/// Serializes `MsoTriState` as an optional `bool`, with `msoTriStateMixed` as `None`.
+///
+/// `msoCTrue` and `msoTriStateToggle` can't be serialized.
+pub mod nullable_bool {
+    use crate::MsoTriState;
+    use ::serde::de::{Deserialize, Deserializer};
+    use ::serde::ser::{self, Serializer};
+    use core::convert::TryFrom;
+
+    /// Serializes `msoTrue` and `msoFalse` as `bool`, and `msoTriStateMixed` as `None`.
+    pub fn serialize<S: Serializer>(m: &MsoTriState, serializer: S) -> Result<S::Ok, S::Error> {
+        match m {
+            MsoTriState::msoTriStateMixed => serializer.serialize_none(),
+            &m => {
+                let b = bool::try_from(m).map_err(ser::Error::custom)?;
+                serializer.serialize_some(&b)
+            }
+        }
+    }
+
+    /// Deserializes an optional `bool`, with `None` as `msoTriStateMixed`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MsoTriState, D::Error> {
+        Ok(match Option::<bool>::deserialize(deserializer)? {
+            Some(b) => b.into(),
+            None => MsoTriState::msoTriStateMixed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MsoTriState::{self, *};
+    use ::serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Int(#[serde(with = "super::int")] MsoTriState);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct NullableBool(#[serde(with = "super::nullable_bool")] MsoTriState);
+
+    #[test]
+    fn name() {
+        for &m in &MsoTriState::ALL {
+            let json = serde_json::to_string(&m).unwrap();
+            assert_eq!(json, format!("\"{}\"", m));
+            assert_eq!(serde_json::from_str::<MsoTriState>(&json).unwrap(), m);
+        }
+        assert!(serde_json::from_str::<MsoTriState>("\"true\"").is_err());
+        assert!(serde_json::from_str::<MsoTriState>("-1").is_err());
+    }
+
+    #[test]
+    fn int() {
+        for &m in &MsoTriState::ALL {
+            let json = serde_json::to_string(&Int(m)).unwrap();
+            assert_eq!(json, m.to_i32().to_string());
+            assert_eq!(serde_json::from_str::<Int>(&json).unwrap(), Int(m));
+        }
+        assert!(serde_json::from_str::<Int>("2").is_err());
+        assert!(serde_json::from_str::<Int>("18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn nullable_bool() {
+        for &(m, json) in &[
+            (msoTrue, "true"),
+            (msoFalse, "false"),
+            (msoTriStateMixed, "null"),
+        ] {
+            assert_eq!(serde_json::to_string(&NullableBool(m)).unwrap(), json);
+            assert_eq!(
+                serde_json::from_str::<NullableBool>(json).unwrap(),
+                NullableBool(m)
+            );
+        }
+        assert!(serde_json::to_string(&NullableBool(msoCTrue)).is_err());
+        assert!(serde_json::to_string(&NullableBool(msoTriStateToggle)).is_err());
+    }
+}
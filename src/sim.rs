@@ -0,0 +1,365 @@
+//! A tri-state logic gate and bus simulator.
+//!
+//! Each wire carries an `MsoTriState`: `msoTrue` or `msoFalse` when driven, and
+//! `msoTriStateMixed` when undriven (high impedance) or when its drivers conflict. Gates use
+//! the Kleene operators, so an unknown input only makes the output unknown when it matters.
+//!
+//! ```
+//! use mso_tri_state::sim::Netlist;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let mut net = Netlist::new();
+//! let (data, data_driver) = net.input(msoTrue);
+//! let (enable, enable_driver) = net.input(msoFalse);
+//! let bus = net.wire();
+//! net.buffer(data, enable, bus);
+//!
+//! net.settle(10).unwrap();
+//! assert_eq!(net.value(bus), msoTriStateMixed);
+//!
+//! net.set(enable_driver, msoTrue);
+//! net.settle(10).unwrap();
+//! assert_eq!(net.value(bus), msoTrue);
+//!
+//! net.set(data_driver, msoFalse);
+//! net.settle(10).unwrap();
+//! assert_eq!(net.value(bus), msoFalse);
+//! ```
+
+use crate::MsoTriState;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A wire in a `Netlist`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Wire(usize);
+
+/// A constant driver in a `Netlist`, whose value can be changed between steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Driver(usize);
+
+/// The result of resolving every drive on a wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    /// Nothing drives the wire.
+    Undriven,
+    /// Every driver agrees on the value.
+    Driven(MsoTriState),
+    /// Drivers disagree.
+    Contention,
+}
+
+impl Resolution {
+    /// Returns the value of the wire.
+    pub fn value(self) -> MsoTriState {
+        match self {
+            Resolution::Driven(m) => m,
+            Resolution::Undriven | Resolution::Contention => MsoTriState::msoTriStateMixed,
+        }
+    }
+}
+
+/// Resolves the values driven onto a bus.
+///
+/// Values are normalized first. Drivers which drive `msoTriStateMixed` make the wire unknown,
+/// but only disagreeing `msoTrue` and `msoFalse` drivers are contention.
+pub fn resolve<I: IntoIterator<Item = MsoTriState>>(drives: I) -> Resolution {
+    let (mut any_true, mut any_false, mut any_unknown) = (false, false, false);
+    for m in drives {
+        match m.normalize() {
+            MsoTriState::msoTrue => any_true = true,
+            MsoTriState::msoFalse => any_false = true,
+            _ => any_unknown = true,
+        }
+    }
+    match (any_true, any_false, any_unknown) {
+        (true, true, _) => Resolution::Contention,
+        (_, _, true) => Resolution::Driven(MsoTriState::msoTriStateMixed),
+        (true, false, false) => Resolution::Driven(MsoTriState::msoTrue),
+        (false, true, false) => Resolution::Driven(MsoTriState::msoFalse),
+        (false, false, false) => Resolution::Undriven,
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Component {
+    Driver(MsoTriState, Wire),
+    Buffer {
+        input: Wire,
+        enable: Wire,
+        output: Wire,
+    },
+    And(Wire, Wire, Wire),
+    Or(Wire, Wire, Wire),
+    Not(Wire, Wire),
+}
+
+impl Component {
+    /// Returns the wire driven by the component and the value driven, if any.
+    fn drive(&self, values: &[MsoTriState]) -> (Wire, Option<MsoTriState>) {
+        let v = |w: Wire| values[w.0];
+        match *self {
+            Component::Driver(m, output) => (output, Some(m)),
+            Component::Buffer {
+                input,
+                enable,
+                output,
+            } => (
+                output,
+                match v(enable).normalize() {
+                    MsoTriState::msoTrue => Some(v(input)),
+                    MsoTriState::msoFalse => None,
+                    _ => Some(MsoTriState::msoTriStateMixed),
+                },
+            ),
+            Component::And(a, b, output) => (output, Some(v(a) & v(b))),
+            Component::Or(a, b, output) => (output, Some(v(a) | v(b))),
+            Component::Not(a, output) => (output, Some(!v(a))),
+        }
+    }
+}
+
+/// The outcome of one simulation step.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Step {
+    /// Whether any wire changed value.
+    pub changed: bool,
+    /// The wires whose drivers disagreed, in order.
+    pub contention: Vec<Wire>,
+}
+
+/// The error returned when a netlist doesn't settle within the step limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NotSettled(pub usize);
+
+impl fmt::Display for NotSettled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "netlist did not settle within {} steps", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotSettled {}
+
+/// A netlist of wires, drivers, tri-state buffers and gates.
+///
+/// Every component is evaluated from the wire values of the previous step, so feedback loops
+/// behave like gates with unit delay. All wires start undriven.
+#[derive(Clone, Debug, Default)]
+pub struct Netlist {
+    values: Vec<MsoTriState>,
+    components: Vec<Component>,
+}
+
+impl Netlist {
+    /// Creates an empty netlist.
+    pub fn new() -> Netlist {
+        Netlist::default()
+    }
+
+    /// Adds a wire.
+    pub fn wire(&mut self) -> Wire {
+        self.values.push(MsoTriState::msoTriStateMixed);
+        Wire(self.values.len() - 1)
+    }
+
+    /// Adds a driver of `value` onto `output`.
+    pub fn driver(&mut self, value: MsoTriState, output: Wire) -> Driver {
+        self.components.push(Component::Driver(value, output));
+        Driver(self.components.len() - 1)
+    }
+
+    /// Adds a wire and a driver of `value` onto it.
+    pub fn input(&mut self, value: MsoTriState) -> (Wire, Driver) {
+        let wire = self.wire();
+        (wire, self.driver(value, wire))
+    }
+
+    /// Changes the value of a driver.
+    ///
+    /// # Panics
+    /// Panics if `driver` is not a driver of this netlist.
+    pub fn set(&mut self, driver: Driver, value: MsoTriState) {
+        match self.components.get_mut(driver.0) {
+            Some(Component::Driver(m, _)) => *m = value,
+            _ => panic!("not a driver of this netlist"),
+        }
+    }
+
+    /// Adds a tri-state buffer driving `input` onto `output` while `enable` is true.
+    ///
+    /// While `enable` is unknown the buffer drives `msoTriStateMixed`.
+    pub fn buffer(&mut self, input: Wire, enable: Wire, output: Wire) {
+        self.components.push(Component::Buffer {
+            input,
+            enable,
+            output,
+        });
+    }
+
+    /// Adds an AND gate and returns its output wire.
+    pub fn and(&mut self, a: Wire, b: Wire) -> Wire {
+        let output = self.wire();
+        self.components.push(Component::And(a, b, output));
+        output
+    }
+
+    /// Adds an OR gate and returns its output wire.
+    pub fn or(&mut self, a: Wire, b: Wire) -> Wire {
+        let output = self.wire();
+        self.components.push(Component::Or(a, b, output));
+        output
+    }
+
+    /// Adds a NOT gate and returns its output wire.
+    pub fn not(&mut self, a: Wire) -> Wire {
+        let output = self.wire();
+        self.components.push(Component::Not(a, output));
+        output
+    }
+
+    /// Returns the current value of a wire.
+    pub fn value(&self, wire: Wire) -> MsoTriState {
+        self.values[wire.0]
+    }
+
+    /// Evaluates every component once and resolves every wire.
+    pub fn step(&mut self) -> Step {
+        let mut drives = vec![Vec::new(); self.values.len()];
+        for c in &self.components {
+            if let (wire, Some(m)) = c.drive(&self.values) {
+                drives[wire.0].push(m);
+            }
+        }
+
+        let mut step = Step::default();
+        for (i, d) in drives.into_iter().enumerate() {
+            let resolution = resolve(d);
+            if resolution == Resolution::Contention {
+                step.contention.push(Wire(i));
+            }
+            let value = resolution.value();
+            if self.values[i] != value {
+                self.values[i] = value;
+                step.changed = true;
+            }
+        }
+        step
+    }
+
+    /// Steps until no wire changes, returning the last step.
+    ///
+    /// Fails if the netlist is still changing after `max_steps` steps.
+    pub fn settle(&mut self, max_steps: usize) -> Result<Step, NotSettled> {
+        for _ in 0..max_steps {
+            let step = self.step();
+            if !step.changed {
+                return Ok(step);
+            }
+        }
+        Err(NotSettled(max_steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    #[test]
+    fn resolution() {
+        assert_eq!(resolve(vec![]), Resolution::Undriven);
+        assert_eq!(
+            resolve(vec![msoTrue, msoCTrue]),
+            Resolution::Driven(msoTrue)
+        );
+        assert_eq!(
+            resolve(vec![msoTriStateMixed, msoFalse]),
+            Resolution::Driven(msoTriStateMixed)
+        );
+        assert_eq!(resolve(vec![msoTrue, msoFalse]), Resolution::Contention);
+        assert_eq!(
+            resolve(vec![msoTrue, msoTriStateMixed, msoFalse]),
+            Resolution::Contention
+        );
+        assert_eq!(Resolution::Contention.value(), msoTriStateMixed);
+        assert_eq!(Resolution::Undriven.value(), msoTriStateMixed);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a driver of this netlist")]
+    fn set_non_driver() {
+        let mut other = Netlist::new();
+        other.input(msoTrue);
+        let (_, driver) = other.input(msoTrue);
+
+        // The second component of `net` is a gate.
+        let mut net = Netlist::new();
+        let (a, _) = net.input(msoTrue);
+        net.not(a);
+        net.set(driver, msoFalse);
+    }
+
+    #[test]
+    fn gates() {
+        let mut net = Netlist::new();
+        let (a, a_driver) = net.input(msoTrue);
+        let (b, _) = net.input(msoTriStateMixed);
+        let and = net.and(a, b);
+        let or = net.or(a, b);
+        let not = net.not(a);
+
+        net.settle(10).unwrap();
+        assert_eq!(net.value(and), msoTriStateMixed);
+        assert_eq!(net.value(or), msoTrue);
+        assert_eq!(net.value(not), msoFalse);
+
+        net.set(a_driver, msoFalse);
+        net.settle(10).unwrap();
+        assert_eq!(net.value(and), msoFalse);
+        assert_eq!(net.value(or), msoTriStateMixed);
+        assert_eq!(net.value(not), msoTrue);
+    }
+
+    #[test]
+    fn bus_contention() {
+        let mut net = Netlist::new();
+        let bus = net.wire();
+        let (a, _) = net.input(msoTrue);
+        let (b, _) = net.input(msoFalse);
+        let (enable_a, _) = net.input(msoTrue);
+        let (enable_b, enable_b_driver) = net.input(msoFalse);
+        net.buffer(a, enable_a, bus);
+        net.buffer(b, enable_b, bus);
+
+        let step = net.settle(10).unwrap();
+        assert_eq!(net.value(bus), msoTrue);
+        assert!(step.contention.is_empty());
+
+        net.set(enable_b_driver, msoTrue);
+        let step = net.settle(10).unwrap();
+        assert_eq!(net.value(bus), msoTriStateMixed);
+        assert_eq!(step.contention, vec![bus]);
+    }
+
+    #[test]
+    fn oscillation() {
+        // An inverter which drives its own input once the loop is enabled.
+        let mut net = Netlist::new();
+        let w = net.wire();
+        let (seed, _) = net.input(msoFalse);
+        let (enable_seed, enable_seed_driver) = net.input(msoTrue);
+        let (enable_loop, enable_loop_driver) = net.input(msoFalse);
+        net.buffer(seed, enable_seed, w);
+        let inverted = net.not(w);
+        net.buffer(inverted, enable_loop, w);
+
+        net.settle(10).unwrap();
+        assert_eq!(net.value(w), msoFalse);
+
+        net.set(enable_seed_driver, msoFalse);
+        net.set(enable_loop_driver, msoTrue);
+        assert_eq!(net.settle(10), Err(NotSettled(10)));
+    }
+}
@@ -0,0 +1,422 @@
+//! Simplifying expressions without changing their value.
+//!
+//! Every rewrite here holds in Kleene logic, not just in Boolean logic, so a simplified
+//! expression agrees with the original on every assignment, including those where some
+//! variables are `msoTriStateMixed`. This rules out some familiar rewrites: `a & !a` is not
+//! `msoFalse` and `a | !a` is not `msoTrue`, because both are `msoTriStateMixed` when `a` is.
+//!
+//! ```
+//! use mso_tri_state::expr::Expr;
+//!
+//! let e: Expr = "!(a | !b) & (b | c) & msoTrue".parse().unwrap();
+//! assert_eq!(e.simplify().to_string(), "!a & b");
+//! ```
+
+use crate::expr::Expr;
+use crate::MsoTriState::{self, *};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+impl Expr {
+    /// Simplifies the expression.
+    ///
+    /// Negations are pushed down to the variables with De Morgan's laws, `a -> b` becomes
+    /// `!a | b`, constants are folded, and repeated and absorbed operands of `&` and `|` are
+    /// removed.
+    pub fn simplify(&self) -> Expr {
+        simplify(self, false)
+    }
+
+    /// Rewrites the expression in disjunctive normal form.
+    ///
+    /// The result can be exponentially larger than the expression.
+    pub fn to_dnf(&self) -> Dnf {
+        let mut terms = dnf(self, false);
+        terms.sort();
+        Dnf { terms }
+    }
+}
+
+/// Simplifies `e`, or `!e` if `negated` is set.
+fn simplify(e: &Expr, negated: bool) -> Expr {
+    match e {
+        Expr::Const(m) if negated => Expr::Const(!*m),
+        Expr::Const(m) => Expr::Const(m.normalize()),
+        Expr::Var(_) if negated => !e.clone(),
+        Expr::Var(_) => e.clone(),
+        Expr::Not(a) => simplify(a, !negated),
+        Expr::And(a, b) => junction(!negated, simplify(a, negated), simplify(b, negated)),
+        Expr::Or(a, b) => junction(negated, simplify(a, negated), simplify(b, negated)),
+        // !(a ^ b) is !a ^ b.
+        Expr::Xor(a, b) => xor(simplify(a, negated), simplify(b, false)),
+        // !(a -> b) is a & !b.
+        Expr::Implies(a, b) => junction(negated, simplify(a, !negated), simplify(b, negated)),
+    }
+}
+
+fn xor(a: Expr, b: Expr) -> Expr {
+    match (&a, &b) {
+        (Expr::Const(msoTriStateMixed), _) | (_, Expr::Const(msoTriStateMixed)) => {
+            Expr::Const(msoTriStateMixed)
+        }
+        (Expr::Const(msoFalse), _) => b,
+        (_, Expr::Const(msoFalse)) => a,
+        (Expr::Const(msoTrue), _) => simplify(&b, true),
+        (_, Expr::Const(msoTrue)) => simplify(&a, true),
+        _ => a ^ b,
+    }
+}
+
+/// Appends the operands of `e` to `out`, looking through nested `&` if `and` is set, or nested
+/// `|` otherwise.
+fn split(e: Expr, and: bool, out: &mut Vec<Expr>) {
+    match e {
+        Expr::And(a, b) if and => {
+            split(*a, and, out);
+            split(*b, and, out);
+        }
+        Expr::Or(a, b) if !and => {
+            split(*a, and, out);
+            split(*b, and, out);
+        }
+        e => out.push(e),
+    }
+}
+
+/// Builds `a & b` if `and` is set, or `a | b` otherwise, from simplified operands.
+fn junction(and: bool, a: Expr, b: Expr) -> Expr {
+    let (unit, zero) = if and {
+        (msoTrue, msoFalse)
+    } else {
+        (msoFalse, msoTrue)
+    };
+    let mut operands = Vec::new();
+    split(a, and, &mut operands);
+    split(b, and, &mut operands);
+
+    let mut kept: Vec<Expr> = Vec::new();
+    for e in operands {
+        match e {
+            Expr::Const(m) if m == unit => continue,
+            Expr::Const(m) if m == zero => return Expr::Const(zero),
+            _ if kept.contains(&e) => continue,
+            _ => kept.push(e),
+        }
+    }
+
+    // An operand absorbs any other whose dual operands include all of its own, as `a` absorbs
+    // `a | c` in `a & (a | c)`.
+    let duals: Vec<Vec<Expr>> = kept
+        .iter()
+        .map(|e| {
+            let mut out = Vec::new();
+            split(e.clone(), !and, &mut out);
+            out
+        })
+        .collect();
+    let absorbed = |i: usize| {
+        (0..kept.len()).any(|j| {
+            j != i
+                && duals[j].iter().all(|d| duals[i].contains(d))
+                && (duals[j].len() < duals[i].len() || j < i)
+        })
+    };
+    let kept: Vec<Expr> = (0..kept.len())
+        .filter(|&i| !absorbed(i))
+        .map(|i| kept[i].clone())
+        .collect();
+
+    kept.into_iter()
+        .reduce(|a, b| if and { a & b } else { a | b })
+        .unwrap_or(Expr::Const(unit))
+}
+
+/// A literal in a `Dnf` term.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Literal {
+    /// The constant `msoTriStateMixed`.
+    Mixed,
+    /// A variable.
+    Pos(String),
+    /// A negated variable.
+    Neg(String),
+}
+
+impl Literal {
+    fn key(&self) -> (Option<&str>, bool) {
+        match self {
+            Literal::Mixed => (None, false),
+            Literal::Pos(name) => (Some(name), false),
+            Literal::Neg(name) => (Some(name), true),
+        }
+    }
+
+    fn complement(&self) -> Literal {
+        match self {
+            Literal::Mixed => Literal::Mixed,
+            Literal::Pos(name) => Literal::Neg(name.clone()),
+            Literal::Neg(name) => Literal::Pos(name.clone()),
+        }
+    }
+
+    /// Converts the literal to an expression.
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            Literal::Mixed => Expr::Const(msoTriStateMixed),
+            Literal::Pos(name) => Expr::Var(name.clone()),
+            Literal::Neg(name) => !Expr::Var(name.clone()),
+        }
+    }
+}
+
+/// Orders by variable name, then puts a variable before its negation. `Literal::Mixed` comes
+/// first.
+impl Ord for Literal {
+    fn cmp(&self, other: &Literal) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for Literal {
+    fn partial_cmp(&self, other: &Literal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// An expression in three-valued disjunctive normal form: an `|` of terms, each an `&` of
+/// literals.
+///
+/// No term is absorbed by another. Besides the usual `t | (t & u) = t`, this uses the Kleene
+/// law that `a & !a` is never true, so `msoTriStateMixed & t` absorbs any term containing `t`
+/// and a complementary pair.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Dnf {
+    terms: Vec<BTreeSet<Literal>>,
+}
+
+impl Dnf {
+    /// Returns the terms, in sorted order. An empty term is `msoTrue`, and no terms is
+    /// `msoFalse`.
+    pub fn terms(&self) -> &[BTreeSet<Literal>] {
+        &self.terms
+    }
+
+    /// Converts the normal form back to an expression.
+    pub fn to_expr(&self) -> Expr {
+        self.terms
+            .iter()
+            .map(|term| {
+                term.iter()
+                    .map(Literal::to_expr)
+                    .reduce(|a, b| a & b)
+                    .unwrap_or(Expr::Const(msoTrue))
+            })
+            .reduce(|a, b| a | b)
+            .unwrap_or(Expr::Const(msoFalse))
+    }
+}
+
+impl fmt::Display for Dnf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_expr())
+    }
+}
+
+type Term = BTreeSet<Literal>;
+
+fn constant(m: MsoTriState) -> Vec<Term> {
+    match m.normalize() {
+        msoTrue => vec![Term::new()],
+        msoFalse => Vec::new(),
+        _ => vec![Some(Literal::Mixed).into_iter().collect()],
+    }
+}
+
+/// Returns the terms of `e`, or of `!e` if `negated` is set.
+fn dnf(e: &Expr, negated: bool) -> Vec<Term> {
+    match e {
+        Expr::Const(m) if negated => constant(!*m),
+        Expr::Const(m) => constant(*m),
+        Expr::Var(name) => {
+            let literal = Literal::Pos(name.clone());
+            let literal = if negated {
+                literal.complement()
+            } else {
+                literal
+            };
+            vec![Some(literal).into_iter().collect()]
+        }
+        Expr::Not(a) => dnf(a, !negated),
+        Expr::And(a, b) if negated => or(dnf(a, true), dnf(b, true)),
+        Expr::And(a, b) => and(&dnf(a, false), &dnf(b, false)),
+        Expr::Or(a, b) if negated => and(&dnf(a, true), &dnf(b, true)),
+        Expr::Or(a, b) => or(dnf(a, false), dnf(b, false)),
+        // a ^ b is (a & !b) | (!a & b), and !(a ^ b) is (a & b) | (!a & !b).
+        Expr::Xor(a, b) => {
+            let (a, not_a) = (dnf(a, false), dnf(a, true));
+            let (b, not_b) = (dnf(b, false), dnf(b, true));
+            if negated {
+                or(and(&a, &b), and(&not_a, &not_b))
+            } else {
+                or(and(&a, &not_b), and(&not_a, &b))
+            }
+        }
+        Expr::Implies(a, b) if negated => and(&dnf(a, false), &dnf(b, true)),
+        Expr::Implies(a, b) => or(dnf(a, true), dnf(b, false)),
+    }
+}
+
+fn and(a: &[Term], b: &[Term]) -> Vec<Term> {
+    let mut terms = Vec::new();
+    for x in a {
+        for y in b {
+            terms.push(x.union(y).cloned().collect());
+        }
+    }
+    reduce(terms)
+}
+
+fn or(mut a: Vec<Term>, b: Vec<Term>) -> Vec<Term> {
+    a.extend(b);
+    reduce(a)
+}
+
+fn is_contradictory(term: &Term) -> bool {
+    term.iter()
+        .any(|l| *l != Literal::Mixed && term.contains(&l.complement()))
+}
+
+/// Returns whether `a | b` is `a`.
+fn absorbs(a: &Term, b: &Term) -> bool {
+    a.is_subset(b)
+        || (a.contains(&Literal::Mixed)
+            && is_contradictory(b)
+            && a.iter().all(|l| *l == Literal::Mixed || b.contains(l)))
+}
+
+fn reduce(terms: Vec<Term>) -> Vec<Term> {
+    let mut terms: Vec<Term> = terms
+        .into_iter()
+        .map(|mut term| {
+            // A complementary pair is never true, so it is at most `msoTriStateMixed`.
+            if is_contradictory(&term) {
+                term.remove(&Literal::Mixed);
+            }
+            term
+        })
+        .collect();
+    terms.sort();
+    terms.dedup();
+    (0..terms.len())
+        .filter(|&i| {
+            !(0..terms.len()).any(|j| {
+                j != i && absorbs(&terms[j], &terms[i]) && (j < i || !absorbs(&terms[i], &terms[j]))
+            })
+        })
+        .map(|i| terms[i].clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Rng;
+
+    /// Asserts that `a` and `b` agree on every assignment of every value to `a`, `b` and `c`.
+    fn assert_agree(x: &Expr, y: &Expr) {
+        for &a in &MsoTriState::ALL {
+            for &b in &MsoTriState::ALL {
+                for &c in &MsoTriState::ALL {
+                    let env = |name: &str| match name {
+                        "a" => Some(a),
+                        "b" => Some(b),
+                        _ => Some(c),
+                    };
+                    assert_eq!(
+                        x.eval(&env),
+                        y.eval(&env),
+                        "{} and {} at a={}, b={}, c={}",
+                        x,
+                        y,
+                        a,
+                        b,
+                        c
+                    );
+                }
+            }
+        }
+    }
+
+    fn parse(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn simplify() {
+        for &(from, to) in &[
+            ("a & msoTrue", "a"),
+            ("a & msoFalse", "msoFalse"),
+            ("a | msoCTrue", "msoTrue"),
+            ("a | msoFalse", "a"),
+            ("a & msoTriStateMixed", "a & msoTriStateMixed"),
+            ("!msoTriStateToggle", "msoTriStateMixed"),
+            ("!!a", "a"),
+            ("!(a & b)", "!a | !b"),
+            ("!(a | !b)", "!a & b"),
+            ("!(a -> b)", "a & !b"),
+            ("a -> b", "!a | b"),
+            ("a & b & a", "a & b"),
+            ("a & (a | c)", "a"),
+            ("(a | c) & a", "a"),
+            ("(a | b) & (a | b | c)", "a | b"),
+            ("(a | b) & (b | a)", "a | b"),
+            ("a & !a", "a & !a"),
+            ("a | !a", "a | !a"),
+            ("a ^ msoTrue", "!a"),
+            ("msoFalse ^ !(a & b)", "!a | !b"),
+            ("a ^ msoTriStateMixed", "msoTriStateMixed"),
+            ("!(a ^ b)", "!a ^ b"),
+        ] {
+            let e = parse(from);
+            assert_eq!(e.simplify().to_string(), to, "{}", from);
+            assert_agree(&e, &e.simplify());
+        }
+    }
+
+    #[test]
+    fn dnf() {
+        for &(from, to) in &[
+            ("msoTrue", "msoTrue"),
+            ("msoFalse", "msoFalse"),
+            ("msoTriStateToggle", "msoTriStateMixed"),
+            ("a & (b | c)", "a & b | a & c"),
+            ("!(a | b)", "!a & !b"),
+            ("a ^ b", "a & !b | !a & b"),
+            ("a -> b", "!a | b"),
+            ("a & (a | b)", "a"),
+            ("a & !a", "a & !a"),
+            ("a & !a | msoTriStateMixed", "msoTriStateMixed"),
+            ("a & !a & b | msoTriStateMixed & b", "msoTriStateMixed & b"),
+            ("a & !a & msoTriStateMixed", "a & !a"),
+            ("msoTriStateMixed & b | b", "b"),
+        ] {
+            let e = parse(from);
+            assert_eq!(e.to_dnf().to_string(), to, "{}", from);
+            assert_agree(&e, &e.to_dnf().to_expr());
+        }
+    }
+
+    #[test]
+    fn agrees_with_original() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..500 {
+            let e = rng.expr(4);
+            assert_agree(&e, &e.simplify());
+            assert_agree(&e, &e.to_dnf().to_expr());
+        }
+    }
+}
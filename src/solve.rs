@@ -0,0 +1,527 @@
+//! Finding assignments that give an expression a value.
+//!
+//! Each variable is encoded as two Boolean variables, one true when it is `msoTrue` and the
+//! other when it is `msoFalse`, with `msoTriStateMixed` when neither is. The Kleene operators
+//! become monotone Boolean circuits over these pairs, which are handed to a CDCL solver with
+//! unit propagation and clause learning.
+//!
+//! ```
+//! use mso_tri_state::expr::Expr;
+//! use mso_tri_state::solve;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let rule: Expr = "bold & !italic".parse().unwrap();
+//! let model = solve::solve(&rule, msoTriStateMixed).unwrap();
+//! assert_eq!(rule.eval(&model), Ok(msoTriStateMixed));
+//!
+//! // `a | !a` is never false, though it is not always true either.
+//! let excluded: Expr = "a | !a".parse().unwrap();
+//! assert_eq!(solve::solve(&excluded, msoFalse), None);
+//! assert!(solve::solve(&excluded, msoTriStateMixed).is_some());
+//! ```
+
+use crate::expr::Expr;
+use crate::MsoTriState::{self, *};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Not;
+
+/// A value for each variable.
+pub type Assignment = BTreeMap<String, MsoTriState>;
+
+/// Finds an assignment to the variables of `e` that makes it evaluate to `target`, or returns
+/// `None` if there is none.
+pub fn solve(e: &Expr, target: MsoTriState) -> Option<Assignment> {
+    let mut enc = Encoder::new();
+    let (t, f) = enc.encode(e);
+    match target.normalize() {
+        msoTrue => enc.sat.add_clause(&[t]),
+        msoFalse => enc.sat.add_clause(&[f]),
+        _ => {
+            enc.sat.add_clause(&[!t]);
+            enc.sat.add_clause(&[!f]);
+        }
+    }
+    enc.solve()
+}
+
+/// Checks that `a` and `b` evaluate to the same value under every assignment, returning one
+/// where they differ if not.
+pub fn equivalent(a: &Expr, b: &Expr) -> Result<(), Assignment> {
+    let mut enc = Encoder::new();
+    let (at, af) = enc.encode(a);
+    let (bt, bf) = enc.encode(b);
+    let t = enc.xor(at, bt);
+    let f = enc.xor(af, bf);
+    let differ = enc.or(t, f);
+    enc.sat.add_clause(&[differ]);
+    enc.solve().map_or(Ok(()), Err)
+}
+
+/// Checks that `b` is `msoTrue` under every assignment that makes `a` `msoTrue`, returning one
+/// where it is not if not.
+pub fn entails(a: &Expr, b: &Expr) -> Result<(), Assignment> {
+    let mut enc = Encoder::new();
+    let (at, _) = enc.encode(a);
+    let (bt, _) = enc.encode(b);
+    enc.sat.add_clause(&[at]);
+    enc.sat.add_clause(&[!bt]);
+    enc.solve().map_or(Ok(()), Err)
+}
+
+/// A Boolean variable, or its negation in the low bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Lit(u32);
+
+impl Lit {
+    fn new(var: usize, negated: bool) -> Lit {
+        Lit((var as u32) << 1 | negated as u32)
+    }
+
+    fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    fn is_negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+/// A CDCL solver for clauses over `Lit`s.
+#[derive(Default)]
+struct Sat {
+    clauses: Vec<Vec<Lit>>,
+    /// The clauses watching each literal, which are visited when it becomes false.
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    phases: Vec<bool>,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    bump: f64,
+    unsatisfiable: bool,
+}
+
+impl Sat {
+    fn new_var(&mut self) -> usize {
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.phases.push(false);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.values.len() - 1
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|v| v != lit.is_negated())
+    }
+
+    fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(!lit.is_negated());
+        self.levels[var] = self.level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Adds a clause. This must be done before solving.
+    fn add_clause(&mut self, lits: &[Lit]) {
+        let mut clause = lits.to_vec();
+        clause.sort_unstable();
+        clause.dedup();
+        if clause.windows(2).any(|w| w[0] == !w[1]) {
+            return;
+        }
+        match clause.len() {
+            0 => self.unsatisfiable = true,
+            1 => match self.value(clause[0]) {
+                Some(true) => {}
+                Some(false) => self.unsatisfiable = true,
+                None => self.assign(clause[0], None),
+            },
+            _ => {
+                self.attach(clause);
+            }
+        }
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    /// Propagates unit clauses, returning a clause that became false, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let falsified = !self.trail[self.propagated];
+            self.propagated += 1;
+            let watching = mem::take(&mut self.watches[falsified.index()]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let other = clause[0];
+                if self.values[other.var()].map(|v| v != other.is_negated()) == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+                let values = &self.values;
+                let replacement = (2..clause.len()).find(|&k| {
+                    let lit = clause[k];
+                    values[lit.var()].map(|v| v != lit.is_negated()) != Some(false)
+                });
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let watch = clause[1];
+                    self.watches[watch.index()].push(index);
+                    continue;
+                }
+                kept.push(index);
+                match self.value(other) {
+                    Some(false) => conflict = Some(index),
+                    _ => self.assign(other, Some(index)),
+                }
+            }
+            self.watches[falsified.index()] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Derives a clause from a conflict at the first unique implication point, returning it with
+    /// its asserting literal first and the level to backtrack to.
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut implied: Option<Lit> = None;
+        loop {
+            for k in 0..self.clauses[conflict].len() {
+                let lit = self.clauses[conflict][k];
+                let var = lit.var();
+                if Some(var) == implied.map(Lit::var) || seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                self.activity[var] += self.bump;
+                if self.levels[var] == self.level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                index -= 1;
+                if seen[self.trail[index].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            seen[lit.var()] = false;
+            implied = Some(lit);
+            pending -= 1;
+            if pending == 0 {
+                break;
+            }
+            conflict = self.reasons[lit.var()].expect("implied literal has a reason");
+        }
+        learnt[0] = !implied.expect("conflict has a literal at the current level");
+
+        let mut backtrack = 0;
+        if learnt.len() > 1 {
+            let highest = (1..learnt.len())
+                .max_by_key(|&k| self.levels[learnt[k].var()])
+                .unwrap();
+            learnt.swap(1, highest);
+            backtrack = self.levels[learnt[1].var()];
+        }
+        (learnt, backtrack)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+        for lit in self.trail.drain(self.trail_limits[level]..) {
+            self.values[lit.var()] = None;
+            self.phases[lit.var()] = !lit.is_negated();
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    /// Returns whether the clauses are satisfiable, leaving every variable assigned if so.
+    fn solve(&mut self) -> bool {
+        if self.unsatisfiable {
+            return false;
+        }
+        self.bump = 1.0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.level() == 0 {
+                    return false;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let index = self.attach(learnt);
+                    self.assign(asserting, Some(index));
+                }
+                self.bump /= 0.95;
+                if self.bump > 1e100 {
+                    self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+                    self.bump *= 1e-100;
+                }
+            } else {
+                let next = (0..self.values.len())
+                    .filter(|&var| self.values[var].is_none())
+                    .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]));
+                let var = match next {
+                    Some(var) => var,
+                    None => return true,
+                };
+                self.trail_limits.push(self.trail.len());
+                self.assign(Lit::new(var, !self.phases[var]), None);
+            }
+        }
+    }
+}
+
+/// Translates expressions into clauses.
+struct Encoder {
+    sat: Sat,
+    /// A literal which is always true.
+    top: Lit,
+    /// The pair of literals for each variable, true when it is `msoTrue` and `msoFalse`.
+    vars: BTreeMap<String, (Lit, Lit)>,
+    /// The output of each `and` gate, so that shared subexpressions share clauses.
+    gates: BTreeMap<(Lit, Lit), Lit>,
+}
+
+impl Encoder {
+    fn new() -> Encoder {
+        let mut sat = Sat::default();
+        let top = Lit::new(sat.new_var(), false);
+        sat.add_clause(&[top]);
+        Encoder {
+            sat,
+            top,
+            vars: BTreeMap::new(),
+            gates: BTreeMap::new(),
+        }
+    }
+
+    fn and(&mut self, a: Lit, b: Lit) -> Lit {
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        if a == !self.top || b == !self.top || a == !b {
+            return !self.top;
+        }
+        if a == self.top || a == b {
+            return b;
+        }
+        if b == self.top {
+            return a;
+        }
+        if let Some(&c) = self.gates.get(&(a, b)) {
+            return c;
+        }
+        let c = Lit::new(self.sat.new_var(), false);
+        self.sat.add_clause(&[!c, a]);
+        self.sat.add_clause(&[!c, b]);
+        self.sat.add_clause(&[c, !a, !b]);
+        self.gates.insert((a, b), c);
+        c
+    }
+
+    fn or(&mut self, a: Lit, b: Lit) -> Lit {
+        !self.and(!a, !b)
+    }
+
+    fn xor(&mut self, a: Lit, b: Lit) -> Lit {
+        let x = self.and(a, !b);
+        let y = self.and(!a, b);
+        self.or(x, y)
+    }
+
+    /// Returns the literals which are true when `e` is `msoTrue` and when it is `msoFalse`.
+    fn encode(&mut self, e: &Expr) -> (Lit, Lit) {
+        match e {
+            Expr::Const(m) => match m.normalize() {
+                msoTrue => (self.top, !self.top),
+                msoFalse => (!self.top, self.top),
+                _ => (!self.top, !self.top),
+            },
+            Expr::Var(name) => {
+                if let Some(&pair) = self.vars.get(name) {
+                    return pair;
+                }
+                let t = Lit::new(self.sat.new_var(), false);
+                let f = Lit::new(self.sat.new_var(), false);
+                self.sat.add_clause(&[!t, !f]);
+                self.vars.insert(name.clone(), (t, f));
+                (t, f)
+            }
+            Expr::Not(a) => {
+                let (t, f) = self.encode(a);
+                (f, t)
+            }
+            Expr::And(a, b) => {
+                let ((at, af), (bt, bf)) = (self.encode(a), self.encode(b));
+                (self.and(at, bt), self.or(af, bf))
+            }
+            Expr::Or(a, b) => {
+                let ((at, af), (bt, bf)) = (self.encode(a), self.encode(b));
+                (self.or(at, bt), self.and(af, bf))
+            }
+            Expr::Xor(a, b) => {
+                let ((at, af), (bt, bf)) = (self.encode(a), self.encode(b));
+                let (x, y) = (self.and(at, bf), self.and(af, bt));
+                let t = self.or(x, y);
+                let (x, y) = (self.and(at, bt), self.and(af, bf));
+                (t, self.or(x, y))
+            }
+            Expr::Implies(a, b) => {
+                let ((at, af), (bt, bf)) = (self.encode(a), self.encode(b));
+                (self.or(af, bt), self.and(at, bf))
+            }
+        }
+    }
+
+    fn solve(mut self) -> Option<Assignment> {
+        if !self.sat.solve() {
+            return None;
+        }
+        let sat = &self.sat;
+        Some(
+            mem::take(&mut self.vars)
+                .into_iter()
+                .map(|(name, (t, f))| {
+                    let m = match (sat.value(t), sat.value(f)) {
+                        (Some(true), _) => msoTrue,
+                        (_, Some(true)) => msoFalse,
+                        _ => msoTriStateMixed,
+                    };
+                    (name, m)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{assignments, Rng};
+    use alloc::format;
+
+    fn parse(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn agrees_with_enumeration() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..300 {
+            let e = rng.expr(4);
+            for &target in &MsoTriState::CORE {
+                let exists = assignments(&MsoTriState::CORE)
+                    .iter()
+                    .any(|env| e.eval(env) == Ok(target));
+                match solve(&e, target) {
+                    Some(model) => assert_eq!(e.eval(&model), Ok(target), "{}", e),
+                    None => assert!(!exists, "{} can be {}", e, target),
+                }
+            }
+            let simplified = e.simplify();
+            assert_eq!(equivalent(&e, &simplified), Ok(()), "{}", e);
+        }
+    }
+
+    #[test]
+    fn equivalence() {
+        let a = parse("a & !a");
+        let model = equivalent(&a, &parse("msoFalse")).unwrap_err();
+        assert_eq!(model["a"], msoTriStateMixed);
+        assert_eq!(equivalent(&parse("a -> b"), &parse("!b -> !a")), Ok(()));
+        assert!(equivalent(&parse("a ^ b"), &parse("a | b")).is_err());
+    }
+
+    #[test]
+    fn entailment() {
+        assert_eq!(entails(&parse("a & b"), &parse("a")), Ok(()));
+        assert_eq!(entails(&parse("a & (a -> b)"), &parse("b")), Ok(()));
+        let model = entails(&parse("a"), &parse("a & b")).unwrap_err();
+        assert_eq!(model["a"], msoTrue);
+        assert_ne!(model["b"], msoTrue);
+        // Nothing is always true in Kleene logic, so nothing follows from `msoTrue`.
+        assert!(entails(&parse("msoTrue"), &parse("a | !a")).is_err());
+    }
+
+    #[test]
+    fn many_variables() {
+        // Far too many assignments to enumerate.
+        let n = 40;
+        let names: Vec<String> = (0..n).map(|i| format!("x{}", i)).collect();
+        let all = names
+            .iter()
+            .map(|name| Expr::var(name))
+            .reduce(|a, b| a & b)
+            .unwrap();
+        let any_not = names
+            .iter()
+            .rev()
+            .map(|name| !Expr::var(name))
+            .reduce(|a, b| a | b)
+            .unwrap();
+        assert_eq!(equivalent(&!all.clone(), &any_not), Ok(()));
+
+        let chain = (1..n)
+            .map(|i| Expr::var(&names[i - 1]).implies(Expr::var(&names[i])))
+            .reduce(|a, b| a & b)
+            .unwrap();
+        let first_to_last = Expr::var(&names[0]).implies(Expr::var(&names[n - 1]));
+        assert_eq!(entails(&chain, &first_to_last), Ok(()));
+        let model = solve(&(chain & Expr::var(&names[0])), msoTrue).unwrap();
+        assert!(model.values().all(|&m| m == msoTrue));
+    }
+}
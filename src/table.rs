@@ -0,0 +1,339 @@
+//! Truth tables of tri-state functions.
+//!
+//! A `TruthTable<N>` lists the value of a function of `N` inputs for every combination of
+//! `msoFalse`, `msoTriStateMixed` and `msoTrue`, in that order, with the first input changing
+//! slowest. It renders as Markdown, CSV or aligned text for pasting into documents.
+//!
+//! ```
+//! use mso_tri_state::table::TruthTable;
+//!
+//! let table = TruthTable::from_expr(&"!a".parse().unwrap(), ["a"]).unwrap();
+//! assert_eq!(
+//!     table.to_markdown(),
+//!     "\
+//! | a                | !a               |
+//! | ---------------- | ---------------- |
+//! | msoFalse         | msoTrue          |
+//! | msoTriStateMixed | msoTriStateMixed |
+//! | msoTrue          | msoFalse         |
+//! "
+//! );
+//! ```
+
+use crate::expr::{Expr, UnboundVariable};
+use crate::ops::rank;
+use crate::MsoTriState::{self, *};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+/// The values of a function `[MsoTriState; N] -> MsoTriState`, with names for its inputs and
+/// output.
+///
+/// Tables compare equal when their functions are, whatever their names.
+#[derive(Clone, Debug)]
+pub struct TruthTable<const N: usize> {
+    inputs: Vec<String>,
+    output: String,
+    values: Vec<MsoTriState>,
+}
+
+impl<const N: usize> TruthTable<N> {
+    /// Tabulates a function, naming the inputs `x1`, `x2` and so on and the output `f`.
+    ///
+    /// Outputs are normalized, so `msoCTrue` becomes `msoTrue` and `msoTriStateToggle` becomes
+    /// `msoTriStateMixed`.
+    pub fn from_fn<F: FnMut([MsoTriState; N]) -> MsoTriState>(mut f: F) -> TruthTable<N> {
+        TruthTable {
+            inputs: (1..=N).map(|i| format!("x{}", i)).collect(),
+            output: "f".to_string(),
+            values: (0..Self::rows_len())
+                .map(|i| f(Self::row(i)).normalize())
+                .collect(),
+        }
+    }
+
+    /// Tabulates an expression, with `inputs` naming the variables in column order and the
+    /// expression itself naming the output.
+    pub fn from_expr(e: &Expr, inputs: [&str; N]) -> Result<TruthTable<N>, UnboundVariable> {
+        let values = (0..Self::rows_len())
+            .map(|i| {
+                let row = Self::row(i);
+                let env = |name: &str| inputs.iter().position(|&n| n == name).map(|k| row[k]);
+                e.eval(&env)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(TruthTable {
+            inputs: inputs.iter().map(|name| name.to_string()).collect(),
+            output: e.to_string(),
+            values,
+        })
+    }
+
+    /// Renames the inputs and output.
+    pub fn with_names(mut self, inputs: [&str; N], output: &str) -> TruthTable<N> {
+        self.inputs = inputs.iter().map(|name| name.to_string()).collect();
+        self.output = output.to_string();
+        self
+    }
+
+    fn rows_len() -> usize {
+        3usize.pow(N as u32)
+    }
+
+    fn row(mut index: usize) -> [MsoTriState; N] {
+        let mut row = [msoTrue; N];
+        for m in row.iter_mut().rev() {
+            *m = MsoTriState::CORE[index % 3];
+            index /= 3;
+        }
+        row
+    }
+
+    /// Returns the number of rows, which is `3^N`.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `false`: there is always at least one row.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the value of the function for some inputs, after normalizing them.
+    pub fn get(&self, inputs: [MsoTriState; N]) -> MsoTriState {
+        let index = inputs
+            .iter()
+            .fold(0, |index, &m| index * 3 + rank(m) as usize);
+        self.values[index]
+    }
+
+    /// Returns the rows, as inputs and the output for them.
+    pub fn rows(&self) -> impl Iterator<Item = ([MsoTriState; N], MsoTriState)> + '_ {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(i, &m)| (Self::row(i), m))
+    }
+
+    /// Returns the name of each input.
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Returns the name of the output.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Returns the header followed by each row, as cells.
+    fn cells(&self) -> impl Iterator<Item = Vec<&str>> + '_ {
+        let header = self
+            .inputs
+            .iter()
+            .map(String::as_str)
+            .chain(Some(self.output.as_str()))
+            .collect();
+        let rows = self.rows().map(|(inputs, output)| {
+            inputs
+                .iter()
+                .chain(Some(&output))
+                .map(|m| m.name())
+                .collect()
+        });
+        Some(header).into_iter().chain(rows)
+    }
+
+    /// Renders the table as a Markdown table with padded columns.
+    ///
+    /// `|` in names is escaped as `\|`.
+    pub fn to_markdown(&self) -> String {
+        let rows: Vec<Vec<String>> = self
+            .cells()
+            .map(|row| row.iter().map(|cell| cell.replace('|', "\\|")).collect())
+            .collect();
+        let widths = widths(&rows);
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        let mut out = String::new();
+        for (i, row) in rows.iter().enumerate() {
+            let padded = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+                .collect();
+            out.push_str(&line(padded));
+            if i == 0 {
+                out.push_str(&line(widths.iter().map(|&w| "-".repeat(w)).collect()));
+            }
+        }
+        out
+    }
+
+    /// Renders the table as CSV, with a header row and quoting where needed.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        for row in self.cells() {
+            let quoted: Vec<String> = row
+                .iter()
+                .map(|cell| {
+                    if cell.contains(&[',', '"', '\n'][..]) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.to_string()
+                    }
+                })
+                .collect();
+            out.push_str(&quoted.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Returns the width of each column, in characters.
+fn widths<S: AsRef<str>>(rows: &[Vec<S>]) -> Vec<usize> {
+    let mut widths = alloc::vec![0; rows[0].len()];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.as_ref().chars().count());
+        }
+    }
+    widths
+}
+
+impl<const N: usize> PartialEq for TruthTable<N> {
+    fn eq(&self, other: &TruthTable<N>) -> bool {
+        self.values == other.values
+    }
+}
+
+impl<const N: usize> Eq for TruthTable<N> {}
+
+impl<const N: usize> Hash for TruthTable<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values.hash(state);
+    }
+}
+
+/// Renders the table as aligned plain text, with a rule under the header.
+impl<const N: usize> fmt::Display for TruthTable<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<Vec<&str>> = self.cells().collect();
+        let widths = widths(&rows);
+        let line = |f: &mut fmt::Formatter<'_>, cells: Vec<String>| {
+            writeln!(f, "{}", cells.join("  ").trim_end())
+        };
+        for (i, row) in rows.iter().enumerate() {
+            let padded = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+                .collect();
+            line(f, padded)?;
+            if i == 0 {
+                line(f, widths.iter().map(|&w| "-".repeat(w)).collect())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Expr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn from_fn() {
+        let and = TruthTable::from_fn(|[a, b]| a & b);
+        assert_eq!(and.len(), 9);
+        assert_eq!(and.get([msoCTrue, msoTriStateToggle]), msoTriStateMixed);
+        assert_eq!(and.get([msoTriStateMixed, msoFalse]), msoFalse);
+        assert_eq!(and.inputs(), ["x1", "x2"]);
+        assert_eq!(and.output(), "f");
+
+        let rows: Vec<_> = and.rows().skip(2).take(4).collect();
+        assert_eq!(
+            rows,
+            [
+                ([msoFalse, msoTrue], msoFalse),
+                ([msoTriStateMixed, msoFalse], msoFalse),
+                ([msoTriStateMixed, msoTriStateMixed], msoTriStateMixed),
+                ([msoTriStateMixed, msoTrue], msoTriStateMixed),
+            ]
+        );
+
+        let constant = TruthTable::from_fn(|[]| msoCTrue);
+        assert_eq!(constant.rows().collect::<Vec<_>>(), [([], msoTrue)]);
+    }
+
+    #[test]
+    fn from_expr() {
+        let table = TruthTable::from_expr(&parse("a -> b"), ["a", "b"]).unwrap();
+        assert_eq!(table, TruthTable::from_fn(|[a, b]| !a | b));
+        assert_ne!(table, TruthTable::from_fn(|[a, b]| a | b));
+        assert_eq!(table.output(), "a -> b");
+        for (inputs, output) in table.rows() {
+            assert_eq!(table.get(inputs), output);
+        }
+
+        // Columns follow `inputs`, not the order of the expression.
+        let swapped = TruthTable::from_expr(&parse("a -> b"), ["b", "a"]).unwrap();
+        assert_eq!(swapped, TruthTable::from_fn(|[b, a]| !a | b));
+
+        assert_eq!(
+            TruthTable::from_expr(&parse("a & c"), ["a", "b"]),
+            Err(UnboundVariable("c".into()))
+        );
+    }
+
+    #[test]
+    fn render() {
+        let table = TruthTable::from_fn(|[a, b]| a ^ b).with_names(["bold", "italic"], "bold, xor");
+        assert_eq!(
+            table.to_string(),
+            "\
+bold              italic            bold, xor
+----------------  ----------------  ----------------
+msoFalse          msoFalse          msoFalse
+msoFalse          msoTriStateMixed  msoTriStateMixed
+msoFalse          msoTrue           msoTrue
+msoTriStateMixed  msoFalse          msoTriStateMixed
+msoTriStateMixed  msoTriStateMixed  msoTriStateMixed
+msoTriStateMixed  msoTrue           msoTriStateMixed
+msoTrue           msoFalse          msoTrue
+msoTrue           msoTriStateMixed  msoTriStateMixed
+msoTrue           msoTrue           msoFalse
+"
+        );
+        let csv = table.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("bold,italic,\"bold, xor\""));
+        assert_eq!(lines.next(), Some("msoFalse,msoFalse,msoFalse"));
+        assert_eq!(lines.count(), 8);
+        assert!(table.to_markdown().starts_with(
+            "| bold             | italic           | bold, xor        |\n\
+             | ---------------- | ---------------- | ---------------- |\n\
+             | msoFalse         | msoFalse         | msoFalse         |\n"
+        ));
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        let table = TruthTable::from_expr(&parse("a | b"), ["a", "b"]).unwrap();
+        let markdown = table.to_markdown();
+        let mut lines = markdown.lines();
+        assert_eq!(
+            lines.next(),
+            Some("| a                | b                | a \\| b           |")
+        );
+        for line in lines {
+            assert_eq!(line.replace("\\|", "").matches('|').count(), 4, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,592 @@
+//! Balanced-ternary integers with `MsoTriState` trits.
+//!
+//! `msoTrue`, `msoFalse` and `msoCTrue` have the Office values -1, 0 and 1, which are exactly the
+//! balanced-ternary digits. `BalancedTernary` has any number of trits, while `FixedTernary` has a
+//! fixed number and wraps on overflow.
+//!
+//! ```
+//! use mso_tri_state::ternary::Ternary27;
+//! use std::convert::TryFrom;
+//!
+//! let a = Ternary27::try_from(8).unwrap();
+//! let b = Ternary27::try_from(-3).unwrap();
+//! assert_eq!(a.to_trit_string(), "+0-");
+//! assert_eq!((a * b).to_string(), "-24");
+//! ```
+
+use crate::MsoTriState;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// A balanced-ternary integer of any size.
+///
+/// The value is stored as trits, least significant first, without trailing `msoFalse` trits.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BalancedTernary {
+    trits: Vec<MsoTriState>,
+}
+
+/// A balanced-ternary integer of `N` trits.
+///
+/// Arithmetic wraps modulo 3<sup>N</sup>. The range is symmetric, so only addition,
+/// subtraction and multiplication can overflow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FixedTernary<const N: usize> {
+    trits: [MsoTriState; N],
+}
+
+/// A balanced-ternary integer of 27 trits.
+pub type Ternary27 = FixedTernary<27>;
+
+/// The error returned when a balanced-ternary conversion fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TernaryError {
+    /// The value is not `msoTrue`, `msoFalse` or `msoCTrue`.
+    NotATrit(MsoTriState),
+    /// The value doesn't fit in the target type.
+    OutOfRange,
+}
+
+impl fmt::Display for TernaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TernaryError::NotATrit(m) => write!(f, "{} is not a trit", m),
+            TernaryError::OutOfRange => write!(f, "value out of range"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TernaryError {}
+
+fn to_trit(d: i8) -> MsoTriState {
+    match d {
+        -1 => MsoTriState::msoTrue,
+        0 => MsoTriState::msoFalse,
+        _ => MsoTriState::msoCTrue,
+    }
+}
+
+fn digits(trits: &[MsoTriState]) -> Vec<i8> {
+    trits.iter().map(|&m| m.to_i32() as i8).collect()
+}
+
+fn check_trits(trits: &[MsoTriState]) -> Result<(), TernaryError> {
+    match trits.iter().find(|&&m| m.to_i32() < -1) {
+        Some(&m) => Err(TernaryError::NotATrit(m)),
+        None => Ok(()),
+    }
+}
+
+fn trim(mut a: Vec<i8>) -> Vec<i8> {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+    a
+}
+
+fn add(a: &[i8], b: &[i8]) -> Vec<i8> {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let s = a.get(i).copied().unwrap_or(0) + b.get(i).copied().unwrap_or(0) + carry;
+        let d = (s + 1).rem_euclid(3) - 1;
+        carry = (s - d) / 3;
+        out.push(d);
+    }
+    if carry != 0 {
+        out.push(carry);
+    }
+    out
+}
+
+fn neg(a: &[i8]) -> Vec<i8> {
+    a.iter().map(|d| -d).collect()
+}
+
+fn sub(a: &[i8], b: &[i8]) -> Vec<i8> {
+    add(a, &neg(b))
+}
+
+fn mul(a: &[i8], b: &[i8]) -> Vec<i8> {
+    let mut out = Vec::new();
+    for (i, &d) in b.iter().enumerate() {
+        if d != 0 {
+            let mut shifted = vec![0; i];
+            shifted.extend(a.iter().map(|x| x * d));
+            out = add(&out, &shifted);
+        }
+    }
+    out
+}
+
+fn sign(a: &[i8]) -> Ordering {
+    a.iter()
+        .rev()
+        .find(|&&d| d != 0)
+        .map_or(Ordering::Equal, |d| d.cmp(&0))
+}
+
+fn compare(a: &[i8], b: &[i8]) -> Ordering {
+    sign(&sub(a, b))
+}
+
+/// Divides, rounding the quotient towards zero like the native integers.
+fn div_rem(a: &[i8], b: &[i8]) -> (Vec<i8>, Vec<i8>) {
+    let b_sign = sign(b);
+    if b_sign == Ordering::Equal {
+        panic!("attempt to divide by zero");
+    }
+    let a = &trim(a.to_vec())[..];
+    let a_negative = sign(a) == Ordering::Less;
+    let a_abs = if a_negative { neg(a) } else { a.to_vec() };
+    let b_abs = if b_sign == Ordering::Less {
+        neg(b)
+    } else {
+        b.to_vec()
+    };
+
+    let mut q = vec![0; a.len()];
+    let mut r = a_abs;
+    for i in (0..a.len()).rev() {
+        let mut shifted = vec![0; i];
+        shifted.extend_from_slice(&b_abs);
+        let mut unit = vec![0; i];
+        unit.push(1);
+        while compare(&r, &shifted) != Ordering::Less {
+            r = sub(&r, &shifted);
+            q = add(&q, &unit);
+        }
+    }
+
+    if a_negative != (b_sign == Ordering::Less) {
+        q = neg(&q);
+    }
+    if a_negative {
+        r = neg(&r);
+    }
+    (q, r)
+}
+
+fn from_i128(mut n: i128) -> Vec<i8> {
+    let mut out = Vec::new();
+    while n != 0 {
+        let d = match n.rem_euclid(3) {
+            2 => -1,
+            d => d as i8,
+        };
+        out.push(d);
+        n = (n - d as i128) / 3;
+    }
+    out
+}
+
+fn to_i64(a: &[i8]) -> Result<i64, TernaryError> {
+    let mut v: i128 = 0;
+    for &d in a.iter().rev() {
+        v = v
+            .checked_mul(3)
+            .and_then(|v| v.checked_add(d as i128))
+            .ok_or(TernaryError::OutOfRange)?;
+    }
+    i64::try_from(v).map_err(|_| TernaryError::OutOfRange)
+}
+
+fn fmt_trits(a: &[i8]) -> String {
+    let s: String = trim(a.to_vec())
+        .iter()
+        .rev()
+        .map(|&d| match d {
+            -1 => '-',
+            0 => '0',
+            _ => '+',
+        })
+        .collect();
+    if s.is_empty() {
+        "0".to_string()
+    } else {
+        s
+    }
+}
+
+fn fmt_decimal(a: &[i8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Ok(v) = to_i64(a) {
+        return fmt::Display::fmt(&v, f);
+    }
+
+    // Horner's method on base 10^9 limbs of the magnitude. Below the most significant non-zero
+    // trit every prefix is positive, so subtracting a trit never borrows past the top limb.
+    let negative = sign(a) == Ordering::Less;
+    let mut limbs: Vec<u32> = Vec::new();
+    for &d in trim(a.to_vec()).iter().rev() {
+        let mut carry = if negative { -d } else { d } as i64;
+        for limb in limbs.iter_mut() {
+            let v = *limb as i64 * 3 + carry;
+            *limb = v.rem_euclid(1_000_000_000) as u32;
+            carry = v.div_euclid(1_000_000_000);
+        }
+        if carry != 0 {
+            limbs.push(carry as u32);
+        }
+    }
+    let mut decimal = limbs.last().map_or(String::new(), |l| l.to_string());
+    for limb in limbs.iter().rev().skip(1) {
+        decimal.push_str(&format!("{:09}", limb));
+    }
+    f.pad_integral(!negative, "", &decimal)
+}
+
+impl BalancedTernary {
+    /// Creates an integer from trits, least significant first.
+    pub fn from_trits(trits: Vec<MsoTriState>) -> Result<BalancedTernary, TernaryError> {
+        check_trits(&trits)?;
+        Ok(BalancedTernary::from_digits(digits(&trits)))
+    }
+
+    fn from_digits(d: Vec<i8>) -> BalancedTernary {
+        BalancedTernary {
+            trits: trim(d).into_iter().map(to_trit).collect(),
+        }
+    }
+
+    fn digits(&self) -> Vec<i8> {
+        digits(&self.trits)
+    }
+
+    /// Returns the trits, least significant first, without trailing `msoFalse` trits.
+    pub fn trits(&self) -> &[MsoTriState] {
+        &self.trits
+    }
+
+    /// Returns the trits as a string of `+`, `0` and `-`, most significant first.
+    pub fn to_trit_string(&self) -> String {
+        fmt_trits(&self.digits())
+    }
+}
+
+impl From<i64> for BalancedTernary {
+    fn from(n: i64) -> BalancedTernary {
+        BalancedTernary::from_digits(from_i128(n as i128))
+    }
+}
+
+impl TryFrom<&BalancedTernary> for i64 {
+    type Error = TernaryError;
+
+    fn try_from(n: &BalancedTernary) -> Result<i64, TernaryError> {
+        to_i64(&n.digits())
+    }
+}
+
+impl fmt::Display for BalancedTernary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_decimal(&self.digits(), f)
+    }
+}
+
+impl Ord for BalancedTernary {
+    fn cmp(&self, other: &BalancedTernary) -> Ordering {
+        compare(&self.digits(), &other.digits())
+    }
+}
+
+impl PartialOrd for BalancedTernary {
+    fn partial_cmp(&self, other: &BalancedTernary) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> FixedTernary<N> {
+    /// Creates an integer from trits, least significant first.
+    pub fn from_trits(trits: [MsoTriState; N]) -> Result<FixedTernary<N>, TernaryError> {
+        check_trits(&trits)?;
+        Ok(FixedTernary { trits })
+    }
+
+    /// Keeps the low `N` digits, which is the value modulo 3<sup>N</sup>.
+    fn from_digits(d: Vec<i8>) -> FixedTernary<N> {
+        let mut trits = [MsoTriState::msoFalse; N];
+        for (t, d) in trits.iter_mut().zip(d) {
+            *t = to_trit(d);
+        }
+        FixedTernary { trits }
+    }
+
+    fn digits(&self) -> Vec<i8> {
+        digits(&self.trits)
+    }
+
+    /// Returns the trits, least significant first.
+    pub fn trits(&self) -> &[MsoTriState; N] {
+        &self.trits
+    }
+
+    /// Returns the trits as a string of `+`, `0` and `-`, most significant first, without
+    /// leading zeros.
+    pub fn to_trit_string(&self) -> String {
+        fmt_trits(&self.digits())
+    }
+}
+
+impl<const N: usize> Default for FixedTernary<N> {
+    fn default() -> FixedTernary<N> {
+        FixedTernary {
+            trits: [MsoTriState::msoFalse; N],
+        }
+    }
+}
+
+impl<const N: usize> TryFrom<i64> for FixedTernary<N> {
+    type Error = TernaryError;
+
+    fn try_from(n: i64) -> Result<FixedTernary<N>, TernaryError> {
+        let d = trim(from_i128(n as i128));
+        if d.len() > N {
+            return Err(TernaryError::OutOfRange);
+        }
+        Ok(FixedTernary::from_digits(d))
+    }
+}
+
+impl<const N: usize> TryFrom<FixedTernary<N>> for i64 {
+    type Error = TernaryError;
+
+    fn try_from(n: FixedTernary<N>) -> Result<i64, TernaryError> {
+        to_i64(&n.digits())
+    }
+}
+
+impl<const N: usize> From<FixedTernary<N>> for BalancedTernary {
+    fn from(n: FixedTernary<N>) -> BalancedTernary {
+        BalancedTernary::from_digits(n.digits())
+    }
+}
+
+impl<const N: usize> TryFrom<&BalancedTernary> for FixedTernary<N> {
+    type Error = TernaryError;
+
+    fn try_from(n: &BalancedTernary) -> Result<FixedTernary<N>, TernaryError> {
+        if n.trits.len() > N {
+            return Err(TernaryError::OutOfRange);
+        }
+        Ok(FixedTernary::from_digits(n.digits()))
+    }
+}
+
+impl<const N: usize> fmt::Display for FixedTernary<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_decimal(&self.digits(), f)
+    }
+}
+
+impl<const N: usize> Ord for FixedTernary<N> {
+    fn cmp(&self, other: &FixedTernary<N>) -> Ordering {
+        self.trits
+            .iter()
+            .rev()
+            .zip(other.trits.iter().rev())
+            .map(|(a, b)| (a.to_i32() as i8).cmp(&(b.to_i32() as i8)))
+            .find(|&o| o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<const N: usize> PartialOrd for FixedTernary<N> {
+    fn partial_cmp(&self, other: &FixedTernary<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+macro_rules! impl_binary_ops {
+    ($(impl$(<$n:ident>)? for $t:ty;)*) => {
+        $(
+            impl$(<const $n: usize>)? Add for $t {
+                type Output = $t;
+
+                fn add(self, rhs: $t) -> $t {
+                    <$t>::from_digits(add(&self.digits(), &rhs.digits()))
+                }
+            }
+
+            impl$(<const $n: usize>)? Sub for $t {
+                type Output = $t;
+
+                fn sub(self, rhs: $t) -> $t {
+                    <$t>::from_digits(sub(&self.digits(), &rhs.digits()))
+                }
+            }
+
+            impl$(<const $n: usize>)? Mul for $t {
+                type Output = $t;
+
+                fn mul(self, rhs: $t) -> $t {
+                    <$t>::from_digits(mul(&self.digits(), &rhs.digits()))
+                }
+            }
+
+            impl$(<const $n: usize>)? Div for $t {
+                type Output = $t;
+
+                fn div(self, rhs: $t) -> $t {
+                    <$t>::from_digits(div_rem(&self.digits(), &rhs.digits()).0)
+                }
+            }
+
+            impl$(<const $n: usize>)? Rem for $t {
+                type Output = $t;
+
+                fn rem(self, rhs: $t) -> $t {
+                    <$t>::from_digits(div_rem(&self.digits(), &rhs.digits()).1)
+                }
+            }
+
+            impl$(<const $n: usize>)? Neg for $t {
+                type Output = $t;
+
+                fn neg(self) -> $t {
+                    <$t>::from_digits(neg(&self.digits()))
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_ops! {
+    impl for BalancedTernary;
+    impl<N> for FixedTernary<N>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    /// A xorshift generator, so the properties below are checked against the same values on
+    /// every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> i64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 as i64
+        }
+
+        /// Returns a value which, like its products with others, fits in 27 trits.
+        fn small(&mut self) -> i64 {
+            self.next() % 1_000_000
+        }
+    }
+
+    #[test]
+    fn trits() {
+        let n = BalancedTernary::from(8);
+        assert_eq!(n.trits(), &[msoTrue, msoFalse, msoCTrue]);
+        assert_eq!(n.to_trit_string(), "+0-");
+        assert_eq!(BalancedTernary::from(0).to_trit_string(), "0");
+        assert_eq!(BalancedTernary::from(0).trits(), &[]);
+        assert_eq!(
+            BalancedTernary::from_trits(vec![msoCTrue, msoFalse, msoFalse]),
+            Ok(BalancedTernary::from(1))
+        );
+        assert_eq!(
+            BalancedTernary::from_trits(vec![msoTriStateMixed]),
+            Err(TernaryError::NotATrit(msoTriStateMixed))
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        for &n in &[0, 1, -1, 13, -40, i64::MAX, i64::MIN] {
+            let b = BalancedTernary::from(n);
+            assert_eq!(i64::try_from(&b), Ok(n));
+            assert_eq!(b.to_string(), n.to_string());
+        }
+
+        let max = (3i64.pow(27) - 1) / 2;
+        assert_eq!(i64::try_from(Ternary27::try_from(max).unwrap()), Ok(max));
+        assert_eq!(i64::try_from(Ternary27::try_from(-max).unwrap()), Ok(-max));
+        assert_eq!(Ternary27::try_from(max + 1), Err(TernaryError::OutOfRange));
+
+        let big = BalancedTernary::from(i64::MAX) * BalancedTernary::from(10);
+        assert_eq!(i64::try_from(&big), Err(TernaryError::OutOfRange));
+        assert_eq!(big.to_string(), "92233720368547758070");
+        assert_eq!((-big).to_string(), "-92233720368547758070");
+        assert_eq!(
+            Ternary27::try_from(&BalancedTernary::from(max + 1)),
+            Err(TernaryError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn arbitrary_arithmetic() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..500 {
+            let (a, b) = (rng.next() >> 2, rng.next() >> 2);
+            let (ta, tb) = (BalancedTernary::from(a), BalancedTernary::from(b));
+            let wide = |n: i128| n.to_string();
+
+            assert_eq!(
+                (ta.clone() + tb.clone()).to_string(),
+                wide(a as i128 + b as i128)
+            );
+            assert_eq!(
+                (ta.clone() - tb.clone()).to_string(),
+                wide(a as i128 - b as i128)
+            );
+            assert_eq!(
+                (ta.clone() * tb.clone()).to_string(),
+                wide(a as i128 * b as i128)
+            );
+            assert_eq!((ta.clone() / tb.clone()).to_string(), (a / b).to_string());
+            assert_eq!((ta.clone() % tb.clone()).to_string(), (a % b).to_string());
+            assert_eq!((-ta.clone()).to_string(), (-a).to_string());
+            assert_eq!(ta.cmp(&tb), a.cmp(&b));
+        }
+    }
+
+    #[test]
+    fn fixed_arithmetic() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        let t = |n: i64| Ternary27::try_from(n).unwrap();
+        for _ in 0..500 {
+            let (a, b) = (rng.small(), rng.small());
+            assert_eq!(i64::try_from(t(a) + t(b)), Ok(a + b));
+            assert_eq!(i64::try_from(t(a) - t(b)), Ok(a - b));
+            assert_eq!(i64::try_from(t(a) * t(b)), Ok(a * b));
+            assert_eq!(i64::try_from(t(a) / t(b)), Ok(a / b));
+            assert_eq!(i64::try_from(t(a) % t(b)), Ok(a % b));
+            assert_eq!(i64::try_from(-t(a)), Ok(-a));
+            assert_eq!(t(a).cmp(&t(b)), a.cmp(&b));
+            assert_eq!(t(a).to_string(), a.to_string());
+        }
+    }
+
+    #[test]
+    fn fixed_wrapping() {
+        let modulus = 3i64.pow(27);
+        let max = (modulus - 1) / 2;
+        let t = |n: i64| Ternary27::try_from(n).unwrap();
+        assert_eq!(i64::try_from(t(max) + t(1)), Ok(-max));
+        let square = (max as i128 * max as i128).rem_euclid(modulus as i128) as i64;
+        let square = if square > max {
+            square - modulus
+        } else {
+            square
+        };
+        assert_eq!(i64::try_from(t(max) * t(max)), Ok(square));
+    }
+
+    #[test]
+    #[should_panic(expected = "divide by zero")]
+    fn divide_by_zero() {
+        let _ = BalancedTernary::from(1) / BalancedTernary::from(0);
+    }
+}
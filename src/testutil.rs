@@ -0,0 +1,59 @@
+//! Helpers shared by the unit tests.
+
+use crate::expr::Expr;
+use crate::MsoTriState;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A xorshift generator, so that properties are checked against the same values on every run.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a value below `n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next() >> 32) as usize % n
+    }
+
+    /// Returns a random expression of at most `depth` over the variables `a`, `b` and `c` and
+    /// every constant.
+    pub(crate) fn expr(&mut self, depth: usize) -> Expr {
+        if depth == 0 || self.below(4) == 0 {
+            return match self.below(8) {
+                n @ 0..=4 => Expr::Const(MsoTriState::ALL[n]),
+                n => Expr::var(["a", "b", "c"][n - 5]),
+            };
+        }
+        let a = Box::new(self.expr(depth - 1));
+        let b = Box::new(self.expr(depth - 1));
+        match self.below(5) {
+            0 => Expr::Not(a),
+            1 => Expr::And(a, b),
+            2 => Expr::Or(a, b),
+            3 => Expr::Xor(a, b),
+            _ => Expr::Implies(a, b),
+        }
+    }
+}
+
+/// Returns every assignment of `values` to the variables `a`, `b` and `c`.
+pub(crate) fn assignments(values: &[MsoTriState]) -> Vec<BTreeMap<String, MsoTriState>> {
+    let mut out = Vec::new();
+    for &a in values {
+        for &b in values {
+            for &c in values {
+                let pairs = [("a", a), ("b", b), ("c", c)];
+                out.push(pairs.iter().map(|&(n, m)| (n.into(), m)).collect());
+            }
+        }
+    }
+    out
+}
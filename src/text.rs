@@ -0,0 +1,327 @@
+//! Text runs with tri-state character formatting, as Word models a selection.
+//!
+//! Text is a sequence of runs, each with its own formatting. Querying a range aggregates the
+//! runs it covers, so a range which is partly bold reports `msoTriStateMixed`. Setting a range
+//! applies a command to that aggregate (see `command::State::apply`), so toggling a partly bold
+//! range makes all of it bold. Ranges are in characters.
+//!
+//! ```
+//! use mso_tri_state::text::{Format, FormattedText, Property};
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let mut text = FormattedText::new();
+//! text.push("Hello, ", Format::default());
+//! text.push("world", Format::default().with(Property::Bold, true));
+//! assert_eq!(text.get(0..12, Property::Bold), msoTriStateMixed);
+//!
+//! text.set(0..12, Property::Bold, msoTriStateToggle).unwrap();
+//! assert_eq!(text.get(0..12, Property::Bold), msoTrue);
+//! assert_eq!(text.runs().len(), 1);
+//! ```
+
+use crate::aggregate::Aggregate;
+use crate::command::{ApplyError, Command, State};
+use crate::MsoTriState;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops::Range;
+
+/// A character property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Property {
+    /// `Font.Bold`.
+    Bold,
+    /// `Font.Italic`.
+    Italic,
+    /// `Font.Underline`.
+    Underline,
+    /// `Font.StrikeThrough`.
+    Strikethrough,
+    /// `Font.Superscript`.
+    Superscript,
+    /// `Font.Subscript`.
+    Subscript,
+    /// `Font.SmallCaps`.
+    SmallCaps,
+    /// `Font.AllCaps`.
+    AllCaps,
+}
+
+impl Property {
+    /// Every property.
+    pub const ALL: [Property; 8] = [
+        Property::Bold,
+        Property::Italic,
+        Property::Underline,
+        Property::Strikethrough,
+        Property::Superscript,
+        Property::Subscript,
+        Property::SmallCaps,
+        Property::AllCaps,
+    ];
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The formatting of a run: whether each `Property` is on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Format(u8);
+
+impl Format {
+    /// Returns whether a property is on.
+    pub fn get(self, property: Property) -> bool {
+        self.0 & property.bit() != 0
+    }
+
+    /// Turns a property on or off.
+    pub fn set(&mut self, property: Property, on: bool) {
+        if on {
+            self.0 |= property.bit();
+        } else {
+            self.0 &= !property.bit();
+        }
+    }
+
+    /// Returns the format with a property turned on or off.
+    pub fn with(mut self, property: Property, on: bool) -> Format {
+        self.set(property, on);
+        self
+    }
+}
+
+/// A run of text with uniform formatting.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Run {
+    /// The text.
+    pub text: String,
+    /// The formatting.
+    pub format: Format,
+}
+
+/// Text made of formatted runs.
+///
+/// Runs are never empty, and adjacent runs always differ in format.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FormattedText {
+    runs: Vec<Run>,
+}
+
+impl FormattedText {
+    /// Creates empty text.
+    pub fn new() -> FormattedText {
+        FormattedText::default()
+    }
+
+    /// Appends text with the given format.
+    pub fn push(&mut self, text: &str, format: Format) {
+        if text.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some(last) if last.format == format => last.text.push_str(text),
+            _ => self.runs.push(Run {
+                text: text.into(),
+                format,
+            }),
+        }
+    }
+
+    /// Returns the runs.
+    pub fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+
+    /// Returns the length in characters.
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|r| r.text.chars().count()).sum()
+    }
+
+    /// Returns whether there is no text.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Returns the plain text.
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|r| r.text.as_str()).collect()
+    }
+
+    /// Returns each run with the range of characters it covers.
+    fn spans(&self) -> impl Iterator<Item = (Range<usize>, &Run)> {
+        let mut start = 0;
+        self.runs.iter().map(move |r| {
+            let end = start + r.text.chars().count();
+            let range = start..end;
+            start = end;
+            (range, r)
+        })
+    }
+
+    /// Returns the aggregated state of a property over a range.
+    ///
+    /// An empty range reports the formatting of the character before it, as text typed there
+    /// would have, or of the first character at the start. Empty text reports `msoFalse`.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    pub fn get(&self, range: Range<usize>, property: Property) -> MsoTriState {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "range out of bounds"
+        );
+        if range.is_empty() {
+            let at = range.start.saturating_sub(1);
+            return self
+                .spans()
+                .find(|(r, _)| r.contains(&at))
+                .map_or(MsoTriState::msoFalse, |(_, run)| {
+                    run.format.get(property).into()
+                });
+        }
+        self.spans()
+            .filter(|(r, _)| r.start < range.end && range.start < r.end)
+            .map(|(_, run)| run.format.get(property))
+            .aggregate()
+    }
+
+    /// Applies a command to a property over a range, splitting and merging runs as needed.
+    ///
+    /// The command is applied to the aggregated state of the range, and the result is given to
+    /// every character in it. Fails if `command` is `msoTriStateMixed`.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds.
+    pub fn set(
+        &mut self,
+        range: Range<usize>,
+        property: Property,
+        command: MsoTriState,
+    ) -> Result<(), ApplyError> {
+        let command = Command::try_from(command)?;
+        let state = State::try_from(self.get(range.clone(), property))?;
+        let on = state.apply(command) == State::True;
+
+        let mut text = FormattedText::new();
+        for (r, run) in self.spans() {
+            let mut chars = run.text.chars();
+            let before: String = chars
+                .by_ref()
+                .take(range.start.saturating_sub(r.start))
+                .collect();
+            let inside: String = chars
+                .by_ref()
+                .take(
+                    range
+                        .end
+                        .min(r.end)
+                        .saturating_sub(range.start.max(r.start)),
+                )
+                .collect();
+            let after: String = chars.collect();
+
+            text.push(&before, run.format);
+            text.push(&inside, run.format.with(property, on));
+            text.push(&after, run.format);
+        }
+        *self = text;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    fn bold() -> Format {
+        Format::default().with(Property::Bold, true)
+    }
+
+    /// Builds "aaabbbccc" with only "bbb" bold.
+    fn text() -> FormattedText {
+        let mut text = FormattedText::new();
+        text.push("aaa", Format::default());
+        text.push("bbb", bold());
+        text.push("ccc", Format::default());
+        text
+    }
+
+    #[test]
+    fn format() {
+        let mut f = Format::default();
+        for &p in &Property::ALL {
+            assert!(!f.get(p));
+            f.set(p, true);
+            assert!(f.get(p));
+        }
+        f.set(Property::Italic, false);
+        assert!(!f.get(Property::Italic));
+        assert!(f.get(Property::Bold));
+    }
+
+    #[test]
+    fn get() {
+        let text = text();
+        assert_eq!(text.get(3..6, Property::Bold), msoTrue);
+        assert_eq!(text.get(0..3, Property::Bold), msoFalse);
+        assert_eq!(text.get(2..4, Property::Bold), msoTriStateMixed);
+        assert_eq!(text.get(0..9, Property::Italic), msoFalse);
+        assert_eq!(text.get(5..5, Property::Bold), msoTrue);
+        assert_eq!(text.get(6..6, Property::Bold), msoTrue);
+        assert_eq!(text.get(0..0, Property::Bold), msoFalse);
+        assert_eq!(FormattedText::new().get(0..0, Property::Bold), msoFalse);
+    }
+
+    #[test]
+    fn set_splits_and_merges() {
+        let mut text = text();
+        text.set(1..2, Property::Italic, msoTrue).unwrap();
+        assert_eq!(text.runs().len(), 5);
+        assert_eq!(text.runs()[1].text, "a");
+        assert!(text.runs()[1].format.get(Property::Italic));
+
+        text.set(0..9, Property::Italic, msoTriStateToggle).unwrap();
+        assert_eq!(text.get(0..9, Property::Italic), msoTrue);
+        assert_eq!(text.runs().len(), 3);
+
+        text.set(2..7, Property::Bold, msoTriStateToggle).unwrap();
+        assert_eq!(text.runs().len(), 3);
+        assert_eq!(text.runs()[1].text, "abbbc");
+
+        text.set(2..7, Property::Bold, msoTriStateToggle).unwrap();
+        assert_eq!(text.runs().len(), 1);
+        assert_eq!(text.text(), "aaabbbccc");
+        assert_eq!(text.get(0..9, Property::Bold), msoFalse);
+    }
+
+    #[test]
+    fn set_commands() {
+        let mut text = text();
+        text.set(0..9, Property::Bold, msoCTrue).unwrap();
+        assert_eq!(text.get(0..9, Property::Bold), msoTrue);
+        text.set(0..9, Property::Bold, msoFalse).unwrap();
+        assert_eq!(text.get(0..9, Property::Bold), msoFalse);
+        assert_eq!(
+            text.set(0..9, Property::Bold, msoTriStateMixed),
+            Err(ApplyError::NotACommand(msoTriStateMixed))
+        );
+    }
+
+    #[test]
+    fn multibyte() {
+        let mut text = FormattedText::new();
+        text.push("héllo", Format::default());
+        text.set(1..2, Property::Underline, msoTrue).unwrap();
+        assert_eq!(text.runs()[1].text, "é");
+        assert_eq!(text.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "range out of bounds")]
+    fn out_of_bounds() {
+        text().get(0..10, Property::Bold);
+    }
+}
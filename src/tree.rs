@@ -0,0 +1,501 @@
+//! A tree of checkboxes with mixed-state propagation.
+//!
+//! Leaves are checked (`msoTrue`) or unchecked (`msoFalse`). Every other node aggregates its
+//! children, showing `msoTriStateMixed` when they disagree. Setting a node applies a command
+//! (see `command::State::apply`) and cascades the result to every descendant.
+//!
+//! ```
+//! use mso_tri_state::tree::CheckTree;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let mut tree = CheckTree::new("fonts");
+//! let serif = tree.add_child(tree.root(), "serif");
+//! let sans = tree.add_child(tree.root(), "sans");
+//!
+//! tree.set(serif, msoTrue).unwrap();
+//! assert_eq!(tree.state(tree.root()), msoTriStateMixed);
+//!
+//! tree.set(tree.root(), msoTriStateToggle).unwrap();
+//! assert_eq!(tree.state(sans), msoTrue);
+//! assert_eq!(tree.to_string(), "[x] fonts\n  [x] serif\n  [x] sans\n");
+//! ```
+
+use crate::aggregate::Aggregate;
+use crate::command::{ApplyError, Command, State};
+use crate::MsoTriState;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+/// A node in a `CheckTree`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Node {
+    label: String,
+    state: MsoTriState,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// A tree of labelled checkboxes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckTree {
+    nodes: Vec<Node>,
+}
+
+/// A difference between two `CheckTree`s, found by `CheckTree::diff`.
+///
+/// Nodes are identified by the labels on the path from the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The node is only in the new tree.
+    Added(Vec<String>),
+    /// The node is only in the old tree.
+    Removed(Vec<String>),
+    /// The node's state changed.
+    Changed {
+        /// The labels on the path to the node.
+        path: Vec<String>,
+        /// The old state.
+        from: MsoTriState,
+        /// The new state.
+        to: MsoTriState,
+    },
+}
+
+impl CheckTree {
+    /// Creates a tree with an unchecked root.
+    pub fn new(label: &str) -> CheckTree {
+        CheckTree {
+            nodes: vec![Node {
+                label: label.to_string(),
+                state: MsoTriState::msoFalse,
+                parent: None,
+                children: Vec::new(),
+            }],
+        }
+    }
+
+    /// Returns the root.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Adds an unchecked child as the last child of `parent`, and returns it.
+    pub fn add_child(&mut self, parent: NodeId, label: &str) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            label: label.to_string(),
+            state: MsoTriState::msoFalse,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.nodes[parent.0].children.push(id);
+        self.update_ancestors(id);
+        id
+    }
+
+    /// Returns the label of a node.
+    pub fn label(&self, node: NodeId) -> &str {
+        &self.nodes[node.0].label
+    }
+
+    /// Returns the state of a node.
+    pub fn state(&self, node: NodeId) -> MsoTriState {
+        self.nodes[node.0].state
+    }
+
+    /// Returns the parent of a node, or `None` for the root.
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node.0].parent
+    }
+
+    /// Returns the children of a node, in order.
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node.0].children
+    }
+
+    /// Returns the labels on the path from the root to a node.
+    pub fn path(&self, node: NodeId) -> Vec<String> {
+        let mut path = Vec::new();
+        let mut n = Some(node);
+        while let Some(id) = n {
+            path.push(self.nodes[id.0].label.clone());
+            n = self.nodes[id.0].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Applies a command to a node and all of its descendants, then updates its ancestors.
+    ///
+    /// `msoTriStateToggle` checks a node which is unchecked or mixed, and unchecks a node which
+    /// is checked. Fails if `command` is `msoTriStateMixed`.
+    pub fn set(&mut self, node: NodeId, command: MsoTriState) -> Result<(), ApplyError> {
+        let command = Command::try_from(command)?;
+        let state = State::try_from(self.state(node))?;
+        let value = MsoTriState::from(state.apply(command));
+
+        let mut stack = vec![node];
+        while let Some(id) = stack.pop() {
+            self.nodes[id.0].state = value;
+            stack.extend(&self.nodes[id.0].children);
+        }
+        self.update_ancestors(node);
+        Ok(())
+    }
+
+    fn update_ancestors(&mut self, node: NodeId) {
+        let mut n = self.nodes[node.0].parent;
+        while let Some(id) = n {
+            let state = self.nodes[id.0]
+                .children
+                .iter()
+                .map(|c| self.nodes[c.0].state)
+                .aggregate();
+            if self.nodes[id.0].state == state {
+                break;
+            }
+            self.nodes[id.0].state = state;
+            n = self.nodes[id.0].parent;
+        }
+    }
+
+    /// Returns every node and its depth, in depth-first pre-order.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, usize)> + '_ {
+        let mut stack = vec![(self.root(), 0)];
+        core::iter::from_fn(move || {
+            let (id, depth) = stack.pop()?;
+            stack.extend(
+                self.nodes[id.0]
+                    .children
+                    .iter()
+                    .rev()
+                    .map(|&c| (c, depth + 1)),
+            );
+            Some((id, depth))
+        })
+    }
+
+    /// Returns the changes from `self` to `other`, in depth-first pre-order.
+    ///
+    /// Children are matched by label. Children with the same label are matched in order.
+    pub fn diff(&self, other: &CheckTree) -> Vec<Change> {
+        let mut changes = Vec::new();
+        if self.label(self.root()) != other.label(other.root()) {
+            changes.push(Change::Removed(self.path(self.root())));
+            changes.push(Change::Added(other.path(other.root())));
+        } else {
+            self.diff_node(self.root(), other, other.root(), &mut changes);
+        }
+        changes
+    }
+
+    fn diff_node(&self, a: NodeId, other: &CheckTree, b: NodeId, changes: &mut Vec<Change>) {
+        if self.state(a) != other.state(b) {
+            changes.push(Change::Changed {
+                path: self.path(a),
+                from: self.state(a),
+                to: other.state(b),
+            });
+        }
+
+        let mut unmatched: Vec<NodeId> = other.children(b).to_vec();
+        for &child in self.children(a) {
+            match unmatched
+                .iter()
+                .position(|&c| other.label(c) == self.label(child))
+            {
+                Some(i) => {
+                    let matched = unmatched.remove(i);
+                    self.diff_node(child, other, matched, changes);
+                }
+                None => changes.push(Change::Removed(self.path(child))),
+            }
+        }
+        for c in unmatched {
+            changes.push(Change::Added(other.path(c)));
+        }
+    }
+}
+
+/// Renders the tree as an outline, one node per line, indented by two spaces per level and
+/// marked `[x]`, `[ ]` or `[-]` for checked, unchecked and mixed.
+///
+/// Backslashes and line breaks in labels are escaped as `\\`, `\n` and `\r`.
+impl fmt::Display for CheckTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, depth) in self.iter() {
+            let marker = match self.state(id) {
+                MsoTriState::msoFalse => "[ ]",
+                MsoTriState::msoTriStateMixed => "[-]",
+                _ => "[x]",
+            };
+            write!(f, "{:indent$}{} ", "", marker, indent = depth * 2)?;
+            for c in self.label(id).chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    c => write!(f, "{}", c)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reverses the escaping of a label by `Display`.
+fn unescape(s: &str) -> Option<String> {
+    let mut label = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        label.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(label)
+}
+
+/// The error returned when parsing a `CheckTree` fails, with the line number it failed at.
+///
+/// Line 0 means the input was empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParseTreeError(pub usize);
+
+impl fmt::Display for ParseTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid check tree at line {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseTreeError {}
+
+/// Parses the outline printed by `Display`.
+///
+/// The markers of parent nodes must agree with their children.
+impl FromStr for CheckTree {
+    type Err = ParseTreeError;
+
+    fn from_str(s: &str) -> Result<CheckTree, ParseTreeError> {
+        let mut tree: Option<CheckTree> = None;
+        let mut markers = Vec::new();
+        // The most recent node at each depth.
+        let mut ancestors: Vec<NodeId> = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let error = ParseTreeError(i + 1);
+            let content = line.trim_start_matches(' ');
+            let indent = line.len() - content.len();
+            if indent % 2 != 0 || content.len() < 4 || content.as_bytes()[3] != b' ' {
+                return Err(error);
+            }
+            let marker = match &content[..3] {
+                "[ ]" => MsoTriState::msoFalse,
+                "[-]" => MsoTriState::msoTriStateMixed,
+                "[x]" => MsoTriState::msoTrue,
+                _ => return Err(error),
+            };
+            let label = unescape(&content[4..]).ok_or(error)?;
+
+            let depth = indent / 2;
+            let id = match (&mut tree, depth) {
+                (None, 0) => {
+                    tree = Some(CheckTree::new(&label));
+                    NodeId(0)
+                }
+                (Some(t), d) if d >= 1 && d <= ancestors.len() => {
+                    ancestors.truncate(d);
+                    t.add_child(ancestors[d - 1], &label)
+                }
+                _ => return Err(error),
+            };
+            ancestors.push(id);
+            markers.push(marker);
+        }
+
+        let mut tree = tree.ok_or(ParseTreeError(0))?;
+        // Leaves first, so that parents are aggregated from their final children.
+        for id in (0..tree.nodes.len()).rev().map(NodeId) {
+            if tree.children(id).is_empty() {
+                if markers[id.0] == MsoTriState::msoTriStateMixed {
+                    return Err(ParseTreeError(id.0 + 1));
+                }
+                tree.set(id, markers[id.0]).unwrap();
+            }
+        }
+        // Nodes were added in order, so each node's index is its line number less one.
+        match (0..tree.nodes.len()).find(|&i| tree.nodes[i].state != markers[i]) {
+            Some(i) => Err(ParseTreeError(i + 1)),
+            None => Ok(tree),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    /// Builds `root` with children `a` (with children `a1` and `a2`) and `b`.
+    fn tree() -> (CheckTree, [NodeId; 4]) {
+        let mut tree = CheckTree::new("root");
+        let a = tree.add_child(tree.root(), "a");
+        let a1 = tree.add_child(a, "a1");
+        let a2 = tree.add_child(a, "a2");
+        let b = tree.add_child(tree.root(), "b");
+        (tree, [a, a1, a2, b])
+    }
+
+    #[test]
+    fn propagation() {
+        let (mut tree, [a, a1, a2, b]) = tree();
+        let root = tree.root();
+        assert_eq!(tree.state(root), msoFalse);
+
+        tree.set(a1, msoTrue).unwrap();
+        assert_eq!(tree.state(a), msoTriStateMixed);
+        assert_eq!(tree.state(root), msoTriStateMixed);
+
+        tree.set(a2, msoCTrue).unwrap();
+        assert_eq!(tree.state(a), msoTrue);
+        assert_eq!(tree.state(root), msoTriStateMixed);
+
+        tree.set(root, msoTrue).unwrap();
+        assert_eq!(tree.state(b), msoTrue);
+
+        tree.set(a, msoFalse).unwrap();
+        assert_eq!(tree.state(a1), msoFalse);
+        assert_eq!(tree.state(a2), msoFalse);
+        assert_eq!(tree.state(root), msoTriStateMixed);
+
+        let c = tree.add_child(b, "c");
+        assert_eq!(tree.state(c), msoFalse);
+        assert_eq!(tree.state(b), msoFalse);
+        assert_eq!(tree.state(root), msoFalse);
+    }
+
+    #[test]
+    fn toggle() {
+        let (mut tree, [a, a1, _, _]) = tree();
+        tree.set(a1, msoTriStateToggle).unwrap();
+        assert_eq!(tree.state(a), msoTriStateMixed);
+
+        tree.set(a, msoTriStateToggle).unwrap();
+        assert_eq!(tree.state(a), msoTrue);
+        assert_eq!(tree.state(a1), msoTrue);
+
+        tree.set(a, msoTriStateToggle).unwrap();
+        assert_eq!(tree.state(a1), msoFalse);
+
+        assert_eq!(
+            tree.set(a, msoTriStateMixed),
+            Err(ApplyError::NotACommand(msoTriStateMixed))
+        );
+    }
+
+    #[test]
+    fn iter() {
+        let (tree, [a, a1, a2, b]) = tree();
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            vec![(tree.root(), 0), (a, 1), (a1, 2), (a2, 2), (b, 1)]
+        );
+        assert_eq!(tree.path(a2), vec!["root", "a", "a2"]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let (mut tree, [_, a1, _, b]) = tree();
+        tree.set(a1, msoTrue).unwrap();
+        tree.set(b, msoTrue).unwrap();
+        let text = tree.to_string();
+        assert_eq!(text, "[-] root\n  [-] a\n    [x] a1\n    [ ] a2\n  [x] b\n");
+        assert_eq!(text.parse::<CheckTree>(), Ok(tree));
+    }
+
+    #[test]
+    fn escaped_labels() {
+        let mut tree = CheckTree::new("C:\\fonts");
+        tree.add_child(tree.root(), "a\nb");
+        tree.add_child(tree.root(), "c\r");
+        let text = tree.to_string();
+        assert_eq!(text, "[ ] C:\\\\fonts\n  [ ] a\\nb\n  [ ] c\\r\n");
+        assert_eq!(text.parse::<CheckTree>(), Ok(tree));
+        assert_eq!("[x] a\\".parse::<CheckTree>(), Err(ParseTreeError(1)));
+        assert_eq!("[x] a\\t".parse::<CheckTree>(), Err(ParseTreeError(1)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("".parse::<CheckTree>(), Err(ParseTreeError(0)));
+        assert_eq!("[x]root".parse::<CheckTree>(), Err(ParseTreeError(1)));
+        assert_eq!("[x] a\n[x] b".parse::<CheckTree>(), Err(ParseTreeError(2)));
+        assert_eq!(
+            "[x] a\n    [x] b".parse::<CheckTree>(),
+            Err(ParseTreeError(2))
+        );
+        assert_eq!(
+            "[x] a\n  [-] b".parse::<CheckTree>(),
+            Err(ParseTreeError(2))
+        );
+        assert_eq!(
+            "[x] a\n  [x] b\n  [ ] c".parse::<CheckTree>(),
+            Err(ParseTreeError(1))
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let (old, [_, a1, _, _]) = tree();
+        let mut new = old.clone();
+        new.set(a1, msoTrue).unwrap();
+        let c = new.add_child(new.root(), "c");
+        new.set(c, msoTrue).unwrap();
+
+        let mut removed = CheckTree::new("root");
+        let a = removed.add_child(removed.root(), "a");
+        removed.add_child(a, "a1");
+        removed.add_child(a, "a2");
+
+        assert_eq!(old.diff(&old), vec![]);
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                Change::Changed {
+                    path: vec!["root".to_string()],
+                    from: msoFalse,
+                    to: msoTriStateMixed,
+                },
+                Change::Changed {
+                    path: vec!["root".to_string(), "a".to_string()],
+                    from: msoFalse,
+                    to: msoTriStateMixed,
+                },
+                Change::Changed {
+                    path: vec!["root".to_string(), "a".to_string(), "a1".to_string()],
+                    from: msoFalse,
+                    to: msoTrue,
+                },
+                Change::Added(vec!["root".to_string(), "c".to_string()]),
+            ]
+        );
+        assert_eq!(
+            old.diff(&removed),
+            vec![Change::Removed(vec!["root".to_string(), "b".to_string()])]
+        );
+    }
+}
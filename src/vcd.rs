@@ -0,0 +1,419 @@
+//! Value Change Dump export and import of `MsoTriState` signals.
+//!
+//! Signals are written as one-bit wires, so they can be viewed in GTKWave:
+//!
+//! | Value               | VCD |
+//! |---------------------|-----|
+//! | `msoTrue`           | `1` |
+//! | `msoFalse`          | `0` |
+//! | `msoTriStateMixed`  | `x` |
+//! | `msoTriStateToggle` | `z` |
+//! | `msoCTrue`          | `1` |
+//!
+//! `msoCTrue` has no value of its own, so it reads back as `msoTrue`. VCD's `z` has no
+//! `MsoTriState` equivalent either, so it stands for `msoTriStateToggle`, the other value Office
+//! never reports as a state.
+//!
+//! ```
+//! use mso_tri_state::vcd::Trace;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let mut trace = Trace::new("1ns");
+//! let bold = trace.signal("bold");
+//! trace.record(bold, 0, msoFalse);
+//! trace.record(bold, 10, msoTriStateMixed);
+//!
+//! let mut vcd = Vec::new();
+//! trace.write(&mut vcd).unwrap();
+//! assert_eq!(Trace::read(&vcd[..]).unwrap(), trace);
+//! ```
+
+use crate::MsoTriState;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// A signal in a `Trace`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Signal(usize);
+
+/// Named `MsoTriState` signals over time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    timescale: String,
+    names: Vec<String>,
+    changes: Vec<Vec<(u64, MsoTriState)>>,
+}
+
+impl Trace {
+    /// Creates an empty trace with a VCD timescale such as `1ns`.
+    pub fn new(timescale: &str) -> Trace {
+        Trace {
+            timescale: timescale.to_string(),
+            names: Vec::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Returns the timescale.
+    pub fn timescale(&self) -> &str {
+        &self.timescale
+    }
+
+    /// Adds a signal.
+    ///
+    /// # Panics
+    /// Panics if `name` is empty or contains whitespace, since VCD could not read it back.
+    pub fn signal(&mut self, name: &str) -> Signal {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            panic!("invalid signal name {:?}", name);
+        }
+        self.names.push(name.to_string());
+        self.changes.push(Vec::new());
+        Signal(self.names.len() - 1)
+    }
+
+    /// Returns every signal in the order they were added.
+    pub fn signals(&self) -> impl Iterator<Item = Signal> {
+        (0..self.names.len()).map(Signal)
+    }
+
+    /// Returns the signal with the given name.
+    pub fn find(&self, name: &str) -> Option<Signal> {
+        self.names.iter().position(|n| n == name).map(Signal)
+    }
+
+    /// Returns the name of a signal.
+    pub fn name(&self, signal: Signal) -> &str {
+        &self.names[signal.0]
+    }
+
+    /// Records the value of a signal at a time.
+    ///
+    /// Values equal to the signal's previous value are ignored. Recording a second value at the
+    /// same time replaces the first, and drops the change if it then matches the value before.
+    ///
+    /// # Panics
+    /// Panics if `time` is before the signal's last recorded change.
+    pub fn record(&mut self, signal: Signal, time: u64, value: MsoTriState) {
+        let changes = &mut self.changes[signal.0];
+        match changes.last_mut() {
+            Some(&mut (t, _)) if time < t => panic!("time went backwards"),
+            Some((t, v)) if *t == time => {
+                *v = value;
+                let n = changes.len();
+                if n > 1 && changes[n - 2].1 == value {
+                    changes.pop();
+                }
+            }
+            Some(&mut (_, v)) if v == value => {}
+            _ => changes.push((time, value)),
+        }
+    }
+
+    /// Returns the changes of a signal, in time order.
+    pub fn changes(&self, signal: Signal) -> &[(u64, MsoTriState)] {
+        &self.changes[signal.0]
+    }
+
+    /// Returns the value of a signal at a time, if it has been recorded by then.
+    pub fn value_at(&self, signal: Signal, time: u64) -> Option<MsoTriState> {
+        let changes = &self.changes[signal.0];
+        match changes.binary_search_by_key(&time, |&(t, _)| t) {
+            Ok(i) => Some(changes[i].1),
+            Err(0) => None,
+            Err(i) => Some(changes[i - 1].1),
+        }
+    }
+
+    /// Writes the trace in VCD format.
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "$timescale {} $end", self.timescale)?;
+        writeln!(w, "$scope module top $end")?;
+        for (i, name) in self.names.iter().enumerate() {
+            writeln!(w, "$var wire 1 {} {} $end", identifier(i), name)?;
+        }
+        writeln!(w, "$upscope $end")?;
+        writeln!(w, "$enddefinitions $end")?;
+
+        let mut events: Vec<(u64, usize, MsoTriState)> = self
+            .changes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| c.iter().map(move |&(t, v)| (t, i, v)))
+            .collect();
+        events.sort_by_key(|&(t, i, _)| (t, i));
+
+        let mut time = None;
+        for (t, i, v) in events {
+            if time != Some(t) {
+                writeln!(w, "#{}", t)?;
+                time = Some(t);
+            }
+            writeln!(w, "{}{}", encode(v), identifier(i))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a trace in VCD format.
+    ///
+    /// Only one-bit signals are supported. Scopes are flattened, so signal names must be unique.
+    pub fn read<R: BufRead>(r: R) -> Result<Trace, VcdError> {
+        let mut tokens = Vec::new();
+        for line in r.lines() {
+            tokens.extend(line?.split_whitespace().map(str::to_string));
+        }
+        let mut tokens = tokens.into_iter();
+
+        let mut trace = Trace::new("1s");
+        let mut ids: Vec<String> = Vec::new();
+        let mut time = 0;
+        while let Some(token) = tokens.next() {
+            match token.as_str() {
+                "$timescale" => {
+                    let body = section(&mut tokens)?;
+                    trace.timescale = body.concat();
+                }
+                "$var" => {
+                    let body = section(&mut tokens)?;
+                    if body.len() < 4 {
+                        return Err(VcdError::Syntax(format!("$var {}", body.join(" "))));
+                    }
+                    if body[1] != "1" {
+                        return Err(VcdError::Unsupported(format!(
+                            "{}-bit signal {}",
+                            body[1], body[3]
+                        )));
+                    }
+                    trace.signal(&body[3]);
+                    ids.push(body[2].clone());
+                }
+                "$dumpvars" | "$dumpall" | "$dumpon" | "$dumpoff" | "$end" => {}
+                t if t.starts_with('$') => {
+                    section(&mut tokens)?;
+                }
+                t if t.starts_with('#') => {
+                    let next = t[1..]
+                        .parse()
+                        .map_err(|_| VcdError::Syntax(t.to_string()))?;
+                    if next < time {
+                        return Err(VcdError::Syntax(t.to_string()));
+                    }
+                    time = next;
+                }
+                t => {
+                    let value = decode(t.as_bytes()[0])
+                        .ok_or_else(|| VcdError::Unsupported(t.to_string()))?;
+                    let i = ids
+                        .iter()
+                        .position(|id| *id == t[1..])
+                        .ok_or_else(|| VcdError::Syntax(t.to_string()))?;
+                    trace.record(Signal(i), time, value);
+                }
+            }
+        }
+        Ok(trace)
+    }
+}
+
+/// Collects the tokens of a section up to its `$end`.
+fn section<I: Iterator<Item = String>>(tokens: &mut I) -> Result<Vec<String>, VcdError> {
+    let mut body = Vec::new();
+    for token in tokens {
+        if token == "$end" {
+            return Ok(body);
+        }
+        body.push(token);
+    }
+    Err(VcdError::Syntax("missing $end".to_string()))
+}
+
+/// Returns the VCD identifier code of the `i`th signal, using the printable ASCII characters.
+fn identifier(mut i: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            return id;
+        }
+        i -= 1;
+    }
+}
+
+fn encode(m: MsoTriState) -> char {
+    match m {
+        MsoTriState::msoTrue | MsoTriState::msoCTrue => '1',
+        MsoTriState::msoFalse => '0',
+        MsoTriState::msoTriStateMixed => 'x',
+        MsoTriState::msoTriStateToggle => 'z',
+    }
+}
+
+fn decode(c: u8) -> Option<MsoTriState> {
+    match c {
+        b'1' => Some(MsoTriState::msoTrue),
+        b'0' => Some(MsoTriState::msoFalse),
+        b'x' | b'X' => Some(MsoTriState::msoTriStateMixed),
+        b'z' | b'Z' => Some(MsoTriState::msoTriStateToggle),
+        _ => None,
+    }
+}
+
+/// The error returned when reading a VCD file fails.
+#[derive(Debug)]
+pub enum VcdError {
+    /// Reading failed.
+    Io(io::Error),
+    /// The file is malformed at the given token.
+    Syntax(String),
+    /// The file uses a VCD feature which isn't supported, such as vector signals.
+    Unsupported(String),
+}
+
+impl fmt::Display for VcdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VcdError::Io(e) => write!(f, "{}", e),
+            VcdError::Syntax(t) => write!(f, "syntax error at {}", t),
+            VcdError::Unsupported(t) => write!(f, "unsupported: {}", t),
+        }
+    }
+}
+
+impl Error for VcdError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VcdError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VcdError {
+    fn from(e: io::Error) -> VcdError {
+        VcdError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MsoTriState::*;
+
+    #[test]
+    fn write() {
+        let mut trace = Trace::new("1ns");
+        let a = trace.signal("a");
+        let b = trace.signal("b");
+        trace.record(a, 0, msoTrue);
+        trace.record(b, 0, msoFalse);
+        trace.record(a, 5, msoTriStateMixed);
+        trace.record(b, 5, msoFalse);
+        trace.record(b, 7, msoTriStateToggle);
+
+        let mut vcd = Vec::new();
+        trace.write(&mut vcd).unwrap();
+        assert_eq!(
+            String::from_utf8(vcd).unwrap(),
+            "$timescale 1ns $end\n\
+             $scope module top $end\n\
+             $var wire 1 ! a $end\n\
+             $var wire 1 \" b $end\n\
+             $upscope $end\n\
+             $enddefinitions $end\n\
+             #0\n1!\n0\"\n#5\nx!\n#7\nz\"\n"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut trace = Trace::new("10us");
+        let signals: Vec<_> = (0..200).map(|i| trace.signal(&format!("s{}", i))).collect();
+        for (i, &s) in signals.iter().enumerate() {
+            for t in 0..10u64 {
+                let value = [msoTrue, msoFalse, msoTriStateMixed, msoTriStateToggle]
+                    [(i + (t as usize) * (i % 3 + 1)) % 4];
+                trace.record(s, t * 3, value);
+            }
+        }
+
+        let mut vcd = Vec::new();
+        trace.write(&mut vcd).unwrap();
+        assert_eq!(Trace::read(&vcd[..]).unwrap(), trace);
+    }
+
+    #[test]
+    fn overwrite_with_previous_value() {
+        let mut trace = Trace::new("1ns");
+        let a = trace.signal("a");
+        trace.record(a, 0, msoTrue);
+        trace.record(a, 5, msoFalse);
+        trace.record(a, 5, msoTrue);
+        assert_eq!(trace.changes(a), &[(0, msoTrue)]);
+
+        let mut vcd = Vec::new();
+        trace.write(&mut vcd).unwrap();
+        assert_eq!(Trace::read(&vcd[..]).unwrap(), trace);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid signal name")]
+    fn signal_with_whitespace() {
+        Trace::new("1ns").signal("bold text");
+    }
+
+    #[test]
+    fn ctrue_reads_as_true() {
+        let mut trace = Trace::new("1ns");
+        let a = trace.signal("a");
+        trace.record(a, 0, msoCTrue);
+
+        let mut vcd = Vec::new();
+        trace.write(&mut vcd).unwrap();
+        let read = Trace::read(&vcd[..]).unwrap();
+        assert_eq!(read.changes(a), &[(0, msoTrue)]);
+    }
+
+    #[test]
+    fn read() {
+        let vcd = "$date today $end\n\
+                   $timescale 1 ps $end\n\
+                   $scope module top $end\n\
+                   $var wire 1 % clk $end\n\
+                   $upscope $end\n\
+                   $enddefinitions $end\n\
+                   $dumpvars 0% $end\n\
+                   #10\n1%\n#20\nX%\n";
+        let trace = Trace::read(vcd.as_bytes()).unwrap();
+        let clk = trace.find("clk").unwrap();
+        assert_eq!(trace.timescale(), "1ps");
+        assert_eq!(trace.value_at(clk, 5), Some(msoFalse));
+        assert_eq!(trace.value_at(clk, 15), Some(msoTrue));
+        assert_eq!(trace.value_at(clk, 20), Some(msoTriStateMixed));
+
+        let vector = "$var wire 8 ! bus $end";
+        match Trace::read(vector.as_bytes()) {
+            Err(VcdError::Unsupported(_)) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn read_backwards() {
+        let vcd = "$var wire 1 ! a $end\n#10\n1!\n#5\n0!\n";
+        match Trace::read(vcd.as_bytes()) {
+            Err(VcdError::Syntax(t)) => assert_eq!(t, "#5"),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        let ids: std::collections::HashSet<_> = (0..10000).map(identifier).collect();
+        assert_eq!(ids.len(), 10000);
+    }
+}
@@ -0,0 +1,345 @@
+//! A packed vector of `MsoTriState` values.
+//!
+//! Values are stored in three bit planes of 64 values per word: `t` for `msoTrue` and
+//! `msoCTrue`, `f` for `msoFalse`, and `e` to tell `msoCTrue` from `msoTrue` and
+//! `msoTriStateToggle` from `msoTriStateMixed` (which have neither `t` nor `f` set). The Kleene
+//! operators then work a word at a time:
+//!
+//! | Operation | `t`       | `f`       |
+//! |-----------|-----------|-----------|
+//! | `a & b`   | `ta & tb` | `fa \| fb` |
+//! | `a \| b`   | `ta \| tb` | `fa & fb` |
+//! | `!a`      | `fa`      | `ta`      |
+//!
+//! As with the operators on `MsoTriState`, results never have `e` set.
+//!
+//! ```
+//! use mso_tri_state::vec::MsoTriStateVec;
+//! use mso_tri_state::MsoTriState::*;
+//!
+//! let a: MsoTriStateVec = vec![msoTrue, msoFalse, msoTriStateMixed].into_iter().collect();
+//! let b: MsoTriStateVec = vec![msoTrue, msoTrue, msoFalse].into_iter().collect();
+//! let and = &a & &b;
+//! assert_eq!(and.get(0), Some(msoTrue));
+//! assert_eq!(and.count(msoFalse), 2);
+//! assert_eq!(and.aggregate(), msoTriStateMixed);
+//! ```
+
+use crate::MsoTriState;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ops::{BitAnd, BitOr, Not};
+
+const BITS: usize = 64;
+
+/// A vector of `MsoTriState` values, stored in three bits each.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MsoTriStateVec {
+    len: usize,
+    t: Vec<u64>,
+    f: Vec<u64>,
+    e: Vec<u64>,
+}
+
+impl MsoTriStateVec {
+    /// Creates an empty vector.
+    pub fn new() -> MsoTriStateVec {
+        MsoTriStateVec::default()
+    }
+
+    /// Creates a vector of `len` copies of `value`.
+    pub fn from_elem(value: MsoTriState, len: usize) -> MsoTriStateVec {
+        let (t, f, e) = planes(value);
+        let words = len.div_ceil(BITS);
+        let fill = |bit: bool| vec![if bit { !0 } else { 0 }; words];
+        let mut v = MsoTriStateVec {
+            len,
+            t: fill(t),
+            f: fill(f),
+            e: fill(e),
+        };
+        v.clear_tail();
+        v
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a value.
+    pub fn push(&mut self, value: MsoTriState) {
+        if self.len % BITS == 0 {
+            self.t.push(0);
+            self.f.push(0);
+            self.e.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, value);
+    }
+
+    /// Returns the value at an index.
+    pub fn get(&self, index: usize) -> Option<MsoTriState> {
+        if index >= self.len {
+            return None;
+        }
+        let (w, mask) = (index / BITS, 1 << (index % BITS));
+        let bit = |plane: &[u64]| plane[w] & mask != 0;
+        Some(match (bit(&self.t), bit(&self.f), bit(&self.e)) {
+            (true, _, false) => MsoTriState::msoTrue,
+            (true, _, true) => MsoTriState::msoCTrue,
+            (false, true, _) => MsoTriState::msoFalse,
+            (false, false, false) => MsoTriState::msoTriStateMixed,
+            (false, false, true) => MsoTriState::msoTriStateToggle,
+        })
+    }
+
+    /// Sets the value at an index.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: MsoTriState) {
+        assert!(index < self.len, "index out of bounds");
+        let (w, mask) = (index / BITS, 1 << (index % BITS));
+        let (t, f, e) = planes(value);
+        for (plane, bit) in [(&mut self.t, t), (&mut self.f, f), (&mut self.e, e)] {
+            if bit {
+                plane[w] |= mask;
+            } else {
+                plane[w] &= !mask;
+            }
+        }
+    }
+
+    /// Returns an iterator over the values.
+    pub fn iter(&self) -> impl Iterator<Item = MsoTriState> + '_ {
+        (0..self.len).map(move |i| self.get(i).unwrap())
+    }
+
+    /// Returns how many values are equal to `value`.
+    pub fn count(&self, value: MsoTriState) -> usize {
+        let tail = self.tail_mask();
+        (0..self.t.len())
+            .map(|w| {
+                let (t, f, e) = (self.t[w], self.f[w], self.e[w]);
+                let valid = if w + 1 == self.t.len() { tail } else { !0 };
+                let matching = match value {
+                    MsoTriState::msoTrue => t & !e,
+                    MsoTriState::msoCTrue => t & e,
+                    MsoTriState::msoFalse => f,
+                    MsoTriState::msoTriStateMixed => !t & !f & !e & valid,
+                    MsoTriState::msoTriStateToggle => !t & !f & e,
+                };
+                matching.count_ones() as usize
+            })
+            .sum()
+    }
+
+    /// Aggregates the values like `Aggregate::aggregate`.
+    ///
+    /// The result is `msoTrue` if all values are `msoTrue` or `msoCTrue`, `msoFalse` if all are
+    /// `msoFalse` or there are none, and `msoTriStateMixed` otherwise.
+    pub fn aggregate(&self) -> MsoTriState {
+        let true_count = self
+            .t
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>();
+        let false_count = self
+            .f
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>();
+        if true_count == self.len {
+            if self.len == 0 {
+                MsoTriState::msoFalse
+            } else {
+                MsoTriState::msoTrue
+            }
+        } else if false_count == self.len {
+            MsoTriState::msoFalse
+        } else {
+            MsoTriState::msoTriStateMixed
+        }
+    }
+
+    fn tail_mask(&self) -> u64 {
+        match self.len % BITS {
+            0 => !0,
+            r => (1 << r) - 1,
+        }
+    }
+
+    fn clear_tail(&mut self) {
+        let tail = self.tail_mask();
+        for plane in [&mut self.t, &mut self.f, &mut self.e] {
+            if let Some(w) = plane.last_mut() {
+                *w &= tail;
+            }
+        }
+    }
+
+    fn zip_with(
+        &self,
+        rhs: &MsoTriStateVec,
+        t: fn(u64, u64) -> u64,
+        f: fn(u64, u64) -> u64,
+    ) -> MsoTriStateVec {
+        assert_eq!(self.len, rhs.len, "length mismatch");
+        MsoTriStateVec {
+            len: self.len,
+            t: self.t.iter().zip(&rhs.t).map(|(&a, &b)| t(a, b)).collect(),
+            f: self.f.iter().zip(&rhs.f).map(|(&a, &b)| f(a, b)).collect(),
+            e: vec![0; self.e.len()],
+        }
+    }
+}
+
+/// Maps a value to its `t`, `f` and `e` bits.
+fn planes(m: MsoTriState) -> (bool, bool, bool) {
+    match m {
+        MsoTriState::msoTrue => (true, false, false),
+        MsoTriState::msoCTrue => (true, false, true),
+        MsoTriState::msoFalse => (false, true, false),
+        MsoTriState::msoTriStateMixed => (false, false, false),
+        MsoTriState::msoTriStateToggle => (false, false, true),
+    }
+}
+
+impl BitAnd for &MsoTriStateVec {
+    type Output = MsoTriStateVec;
+
+    /// # Panics
+    /// Panics if the lengths differ.
+    fn bitand(self, rhs: &MsoTriStateVec) -> MsoTriStateVec {
+        self.zip_with(rhs, |a, b| a & b, |a, b| a | b)
+    }
+}
+
+impl BitOr for &MsoTriStateVec {
+    type Output = MsoTriStateVec;
+
+    /// # Panics
+    /// Panics if the lengths differ.
+    fn bitor(self, rhs: &MsoTriStateVec) -> MsoTriStateVec {
+        self.zip_with(rhs, |a, b| a | b, |a, b| a & b)
+    }
+}
+
+impl Not for &MsoTriStateVec {
+    type Output = MsoTriStateVec;
+
+    fn not(self) -> MsoTriStateVec {
+        MsoTriStateVec {
+            len: self.len,
+            t: self.f.clone(),
+            f: self.t.clone(),
+            e: vec![0; self.e.len()],
+        }
+    }
+}
+
+impl FromIterator<MsoTriState> for MsoTriStateVec {
+    fn from_iter<I: IntoIterator<Item = MsoTriState>>(iter: I) -> MsoTriStateVec {
+        let mut v = MsoTriStateVec::new();
+        v.extend(iter);
+        v
+    }
+}
+
+impl Extend<MsoTriState> for MsoTriStateVec {
+    fn extend<I: IntoIterator<Item = MsoTriState>>(&mut self, iter: I) {
+        for m in iter {
+            self.push(m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::Aggregate;
+    use crate::MsoTriState::*;
+
+    fn values(len: usize, seed: usize) -> Vec<MsoTriState> {
+        (0..len)
+            .map(|i| MsoTriState::ALL[(i * i + seed) % 5])
+            .collect()
+    }
+
+    #[test]
+    fn push_get_set() {
+        let expected = values(130, 3);
+        let mut v: MsoTriStateVec = expected.iter().copied().collect();
+        assert_eq!(v.len(), 130);
+        assert_eq!(v.iter().collect::<Vec<_>>(), expected);
+        assert_eq!(v.get(130), None);
+
+        v.set(64, msoTriStateToggle);
+        assert_eq!(v.get(64), Some(msoTriStateToggle));
+        v.set(64, msoCTrue);
+        assert_eq!(v.get(64), Some(msoCTrue));
+    }
+
+    #[test]
+    fn logic() {
+        for &len in &[0, 1, 63, 64, 65, 200] {
+            let (a, b) = (values(len, 1), values(len, 4));
+            let (va, vb): (MsoTriStateVec, MsoTriStateVec) =
+                (a.iter().copied().collect(), b.iter().copied().collect());
+
+            let and: Vec<_> = a.iter().zip(&b).map(|(&x, &y)| x & y).collect();
+            let or: Vec<_> = a.iter().zip(&b).map(|(&x, &y)| x | y).collect();
+            let not: Vec<_> = a.iter().map(|&x| !x).collect();
+            assert_eq!((&va & &vb).iter().collect::<Vec<_>>(), and);
+            assert_eq!((&va | &vb).iter().collect::<Vec<_>>(), or);
+            assert_eq!((!&va).iter().collect::<Vec<_>>(), not);
+        }
+    }
+
+    #[test]
+    fn count() {
+        for &len in &[0, 5, 64, 100] {
+            let a = values(len, 2);
+            let v: MsoTriStateVec = a.iter().copied().collect();
+            for &m in &MsoTriState::ALL {
+                assert_eq!(v.count(m), a.iter().filter(|&&x| x == m).count());
+            }
+        }
+        assert_eq!(
+            MsoTriStateVec::from_elem(msoTriStateMixed, 70).count(msoTriStateMixed),
+            70
+        );
+    }
+
+    #[test]
+    fn aggregate() {
+        for &len in &[0, 1, 64, 100] {
+            for &m in &MsoTriState::ALL {
+                let v = MsoTriStateVec::from_elem(m, len);
+                assert_eq!(v.aggregate(), v.iter().aggregate());
+            }
+            let a = values(len, 0);
+            let v: MsoTriStateVec = a.iter().copied().collect();
+            assert_eq!(v.aggregate(), a.into_iter().aggregate());
+        }
+
+        let mut v = MsoTriStateVec::from_elem(msoTrue, 100);
+        v.set(99, msoCTrue);
+        assert_eq!(v.aggregate(), msoTrue);
+        v.set(50, msoFalse);
+        assert_eq!(v.aggregate(), msoTriStateMixed);
+    }
+
+    #[test]
+    #[should_panic(expected = "length mismatch")]
+    fn length_mismatch() {
+        let _ = &MsoTriStateVec::from_elem(msoTrue, 1) & &MsoTriStateVec::new();
+    }
+}
@@ -0,0 +1,42 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "mso_tri_state.h"
+
+int main(void) {
+    MsoTriState m;
+    bool b;
+    char buf[32];
+
+    assert(mso_tri_state_to_int(msoCTrue) == 1);
+    assert(mso_tri_state_to_int(msoFalse) == 0);
+    assert(mso_tri_state_to_int(msoTriStateMixed) == -2);
+    assert(mso_tri_state_to_int(msoTriStateToggle) == -3);
+    assert(mso_tri_state_to_int(msoTrue) == -1);
+
+    assert(mso_tri_state_from_int(-2, &m) == MSO_TRI_STATE_OK && m == msoTriStateMixed);
+    assert(mso_tri_state_from_int(2, &m) == MSO_TRI_STATE_ERROR_INVALID);
+    assert(mso_tri_state_from_int(0, NULL) == MSO_TRI_STATE_ERROR_NULL);
+
+    assert(mso_tri_state_from_bool(true) == msoTrue);
+    assert(mso_tri_state_to_bool(msoFalse, &b) == MSO_TRI_STATE_OK && !b);
+    assert(mso_tri_state_to_bool(msoTriStateMixed, &b) == MSO_TRI_STATE_ERROR_INVALID);
+
+    assert(mso_tri_state_not(msoTrue) == msoFalse);
+    assert(mso_tri_state_and(msoTrue, msoTriStateMixed) == msoTriStateMixed);
+    assert(mso_tri_state_or(msoTrue, msoTriStateMixed) == msoTrue);
+    assert(mso_tri_state_xor(msoTrue, msoFalse) == msoTrue);
+
+    assert(mso_tri_state_to_string(msoTriStateToggle, buf, sizeof buf) == 17);
+    assert(strcmp(buf, "msoTriStateToggle") == 0);
+    assert(mso_tri_state_to_string(msoTrue, buf, 4) == 7);
+    assert(strcmp(buf, "mso") == 0);
+
+    assert(mso_tri_state_parse("msoCTrue", false, &m) == MSO_TRI_STATE_OK && m == msoCTrue);
+    assert(mso_tri_state_parse("toggle", false, &m) == MSO_TRI_STATE_ERROR_INVALID);
+    assert(mso_tri_state_parse("toggle", true, &m) == MSO_TRI_STATE_OK && m == msoTriStateToggle);
+
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,51 @@
+//! Builds the crate as a static library and runs `tests/ffi.c` against it with the system C
+//! compiler.
+//!
+//! Only run on Linux, where the libraries the static library needs are known.
+#![cfg(all(feature = "ffi", target_os = "linux"))]
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    assert!(
+        Command::new(&cc).arg("--version").output().is_ok(),
+        "no C compiler found; set CC"
+    );
+
+    let status = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args([
+            "rustc",
+            "--lib",
+            "--crate-type",
+            "staticlib",
+            "--features",
+            "ffi",
+            "--target-dir",
+        ])
+        .arg(out_dir.join("target"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let exe = out_dir.join("ffi");
+    let status = Command::new(&cc)
+        .arg(manifest_dir.join("tests/ffi.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(out_dir.join("target/debug/libmso_tri_state.a"))
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&exe)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&exe).output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(output.stdout, b"ok\n");
+}